@@ -1,13 +1,13 @@
 <?xml version="1.0" encoding="utf-8"?>
 <feed xmlns="http://www.w3.org/2005/Atom">
-  <id>{{ config.site_url }}</id>
+  <id>{{ lang_url }}</id>
   <title>{{ config.site_name }}</title>
   <updated>{{ now }}</updated>
   <subtitle>{{ config.site_motto }}</subtitle>
   <icon>/media/favicon.png</icon>
   <logo>/media/favicon.png</logo>
-  <link rel="alternate" type="text/html" href="{{ config.site_url }}" />
-  <link rel="self" type="application/atom+xml" href="{{ config.site_url }}/atom.xml" />
+  <link rel="alternate" type="text/html" href="{{ lang_url }}" />
+  <link rel="self" type="application/atom+xml" href="{{ config.site_url }}/{{ feed_path }}" />
   <generator uri="https://github.com/FuGangqiang/mdblog.rs">mdblog.rs</generator>
   {% for post in posts -%}
   <entry>
@@ -16,8 +16,13 @@
     <updated>{{ post.headers.created }}</updated>
     <published>{{ post.headers.created }}</published>
     <link href="{{ config.site_url }}{{ post.url  | urlencode }}"/>
-    <summary>{{ post.headers.description }}</summary>
-    <content type="html" xml:lang="en" xml:base="{{ config.site_url }}">
+    {%- for author in post.authors %}
+    <author>
+      <name>{{ author.name }}</name>
+    </author>
+    {%- endfor %}
+    <summary>{{ post.headers.description | escape }}</summary>
+    <content type="html" xml:lang="{{ post.lang }}" xml:base="{{ config.site_url }}{{ post.url | urlencode }}">
         <![CDATA[
         {{ post.content }}
         ]]>
@@ -0,0 +1,60 @@
+{% extends "base.tpl" %}
+
+{% block title %}
+  <title>{{ author.name }}</title>
+{% endblock title %}
+
+{%- block css %}
+<style>
+  .title {
+    font-size: 1.5rem;
+    margin-left: 1rem;
+  }
+
+  .author img {
+    width: 4rem;
+    height: 4rem;
+    border-radius: 50%;
+  }
+</style>
+{% endblock css -%}
+
+{% block main %}
+  <div class="author">
+    {%- if author.avatar %}
+    <img src="{{ author.avatar }}" alt="{{ author.name }}">
+    {%- endif %}
+    <h1>{{ author.name }}</h1>
+    {%- if author.bio %}
+    <p>{{ author.bio }}</p>
+    {%- endif %}
+    <p>
+    {%- for name, link in author.links %}
+      <a href="{{ link }}">{{ name }}</a>
+    {%- endfor %}
+      <a href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ tag.name | urlencode }}.xml">Feed</a>
+    </p>
+  </div>
+  <article>
+  {%- for post in posts %}
+    <section>
+      <span>{{ post.headers.created | truncate(length=10, end="") }}</span>
+      <span class="title"><a href="{{ config.site_url }}{{ post.url  | urlencode }}">{{ post.title }}</a></span>
+    </section>
+  {%- endfor %}
+  </article>
+
+  <div id="pages">
+  {%- if page.index > 1 %}
+    <a class="prev" href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ pages | nth(n=page.index - 2) | get(key='name') | urlencode }}">« Previous</a>
+  {%- endif -%}
+    <span class="spacer"></span>
+    <span class="info">{{ page.index }} / {{ pages | length }}</span>
+    <span class="spacer"></span>
+  {%- if page.index < pages | length %}
+    <a class="next" href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ pages | nth(n=page.index) | get(key='name') | urlencode }}">Next »</a>
+  {% endif -%}
+  </div>
+{%- endblock main %}
+
+{% block js %}{% endblock js %}
@@ -1,11 +1,12 @@
 <!doctype html>
-<html>
+<html lang="{{ lang }}">
 <head>
   <meta charset="utf-8">
   <meta name="generator" content="mdblog.rs">
   <meta name="viewport" content="width=device-width,initial-scale=1.0,maximum-scale=1.0,shrink-to-fit=no,user-scalable=0">
   {%- block title %}{% endblock title -%}
-  <link rel="stylesheet" href="{{ config.site_url }}/static/main.css">
+  {%- set main_css = static_asset(path="static/main.css") %}
+  <link rel="stylesheet" href="{{ main_css.url }}"{% if main_css.integrity %} integrity="{{ main_css.integrity }}" crossorigin="anonymous"{% endif %}>
   {%- block css %}{% endblock css -%}
 </head>
 <body>
@@ -13,14 +14,23 @@
   <div class="container">
     <div id="site">
       <div id="site-name">
-        <a href="{{ config.site_url }}/index.html" title="{{ config.site_name }}">{{ config.site_name }}</a>
+        <a href="{{ lang_url }}/index.html" title="{{ config.site_name }}">{{ config.site_name }}</a>
       </div>
       <div id="site-motto">{{ config.site_motto }}</div>
     </div>
     <nav id="header-nav">
-      <a href="{{ config.site_url }}/index.html">Blog</a>
-      <a href="{{ config.site_url }}/tags.html">Tags</a>
-      <a href="{{ config.site_url }}/atom.xml">Feed</a>
+      <a href="{{ lang_url }}/index.html">Blog</a>
+      {%- for name, taxonomy in taxonomies %}
+      <a href="{{ lang_url }}/{{ taxonomy.settings.path }}.html">{{ name | capitalize }}</a>
+      {%- endfor %}
+      <a href="{{ lang_url }}/atom.xml">Feed</a>
+      {%- if languages | length > 1 %}
+      {%- for code, url in languages %}
+      {%- if code != lang %}
+      <a href="{{ url }}/index.html">{{ code }}</a>
+      {%- endif %}
+      {%- endfor %}
+      {%- endif %}
     </nav>
     <svg id="menu" viewBox="0 0 1024 1024" version="1.1" xmlns="http://www.w3.org/2000/svg">
       <path d="M128 298.666667h768a42.666667 42.666667 0 0 0 0-85.333334H128a42.666667 42.666667 0 0 0 0 85.333334z m768 170.666666H128a42.666667 42.666667 0 0 0 0 85.333334h768a42.666667 42.666667 0 0 0 0-85.333334z m0 256H128a42.666667 42.666667 0 0 0 0 85.333334h768a42.666667 42.666667 0 0 0 0-85.333334z" fill="#fff"></path>
@@ -26,13 +26,13 @@
 
   <div id="pages">
   {%- if page.index > 1 %}
-    <a class="prev" href="{{ tag_pages | get(key=tag.name) | nth(n=page.index - 2) | get(key='name') | urlencode }}">« Previous</a>
+    <a class="prev" href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ pages | nth(n=page.index - 2) | get(key='name') | urlencode }}">« Previous</a>
   {%- endif -%}
     <span class="spacer"></span>
-    <span class="info">{{ page.index }} / {{ tag_pages | get(key=tag.name) | length }}</span>
+    <span class="info">{{ page.index }} / {{ pages | length }}</span>
     <span class="spacer"></span>
-  {%- if page.index < tag_pages | get(key=tag.name) | length %}
-    <a class="next" href="{{ tag_pages | get(key=tag.name) | nth(n=page.index) | get(key='name') | urlencode }}">Next »</a>
+  {%- if page.index < pages | length %}
+    <a class="next" href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ pages | nth(n=page.index) | get(key='name') | urlencode }}">Next »</a>
   {% endif -%}
   </div>
 {%- endblock main %}
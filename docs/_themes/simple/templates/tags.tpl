@@ -1,7 +1,7 @@
 {% extends "base.tpl" %}
 
 {% block title %}
-  <title>Tags</title>
+  <title>{{ taxonomy.settings.name | capitalize }}</title>
 {% endblock title %}
 
 {%- block css %}
@@ -26,10 +26,10 @@
 {% endblock css -%}
 
 {% block main %}
-  <h1>Tags</h1>
+  <h1>{{ taxonomy.settings.name | capitalize }}</h1>
   <article>
-  {%- for tag in tags %}
-    <a href="{{ config.site_url }}/tags/{{ tag.name | urlencode }}.html">{{ tag.name }}<sup>{{ tag.num }}</sup></a>
+  {%- for tag in terms %}
+    <a href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ tag.name | urlencode }}.html">{{ tag.name }}<sup>{{ tag.num }}</sup></a>
   {%- endfor %}
   </article>
 {%- endblock main %}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384};
+
+use crate::error::Result;
+use crate::utils::write_file;
+
+/// file name of the asset manifest in build directory, not `manifest.json` used by web app manifests
+pub const ASSET_MANIFEST: &str = "static/asset-manifest.json";
+
+/// fingerprinted asset
+#[derive(Debug, Clone, Serialize)]
+pub struct Asset {
+    /// asset path relative to the build directory, such as `static/main.3f9a1c2b.css`
+    pub path: String,
+    /// subresource integrity hash, such as `sha384-...`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub integrity: String,
+}
+
+/// asset manifest of theme static files
+///
+/// the original path, such as `static/main.css`, is mapped to its fingerprinted asset,
+/// and written to `static/asset-manifest.json` of build directory.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AssetManifest {
+    /// write fingerprinted copies of static files
+    #[serde(skip)]
+    fingerprint: bool,
+    /// compute subresource integrity hashes
+    #[serde(skip)]
+    integrity: bool,
+    /// assets keyed by original path
+    #[serde(flatten)]
+    assets: BTreeMap<String, Asset>,
+}
+
+impl AssetManifest {
+    /// create new `AssetManifest`
+    pub fn new(fingerprint: bool, integrity: bool) -> AssetManifest {
+        AssetManifest {
+            fingerprint,
+            integrity,
+            assets: BTreeMap::new(),
+        }
+    }
+
+    /// whether fingerprint or integrity is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.fingerprint || self.integrity
+    }
+
+    /// get the asset of original `path`.
+    pub fn get(&self, path: &str) -> Option<&Asset> {
+        self.assets.get(path.trim_start_matches('/'))
+    }
+
+    /// write static file `path` to `dest_dir`, and its fingerprinted copy if enabled.
+    ///
+    /// the original file is always written, so templates linking to it keep working.
+    pub fn write(&mut self, dest_dir: &Path, path: &str, content: &[u8]) -> Result<()> {
+        write_file(&dest_dir.join(path), content)?;
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let asset_path = if self.fingerprint {
+            let hash = format!("{:x}", Sha256::digest(content));
+            let path = fingerprinted_path(path, &hash[..8]);
+            write_file(&dest_dir.join(&path), content)?;
+            path
+        } else {
+            path.to_string()
+        };
+        let integrity = if self.integrity {
+            format!("sha384-{}", BASE64.encode(Sha384::digest(content)))
+        } else {
+            String::new()
+        };
+        self.assets.insert(
+            path.to_string(),
+            Asset {
+                path: asset_path,
+                integrity,
+            },
+        );
+        Ok(())
+    }
+
+    /// write the manifest to `dest_dir` if enabled.
+    pub fn write_manifest(&self, dest_dir: &Path) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let content = serde_json::to_vec_pretty(self)?;
+        write_file(&dest_dir.join(ASSET_MANIFEST), &content)
+    }
+}
+
+/// insert `hash` before the extension of `path`, `static/main.css` to `static/main.<hash>.css`.
+fn fingerprinted_path(path: &str, hash: &str) -> String {
+    let name_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match path[name_start..].rfind('.') {
+        Some(i) if i > 0 => {
+            let dot = name_start + i;
+            format!("{}.{}{}", &path[..dot], hash, &path[dot..])
+        }
+        _ => format!("{}.{}", path, hash),
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// blog author
+///
+/// declared in `config.toml` `[authors.<id>]` table,
+/// the post header `author: <id>` or `authors: [<id>, ...]` refers to it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Author {
+    /// author id, the key of `authors` table
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// author display name
+    #[serde(default)]
+    pub name: String,
+    /// author biography
+    #[serde(default)]
+    pub bio: String,
+    /// author avatar url
+    #[serde(default)]
+    pub avatar: String,
+    /// author links, `github = "https://github.com/<id>"`
+    #[serde(default)]
+    pub links: BTreeMap<String, String>,
+}
+
+impl Author {
+    /// create new `Author` with only id, the display name is the id.
+    pub fn new(id: &str) -> Author {
+        Author {
+            id: id.to_string(),
+            name: id.to_string(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::utils::write_file;
+
+/// diagram renderer of fenced code blocks
+///
+/// the code block language, such as `dot` and `mermaid`, is mapped to a local command,
+/// which reads the diagram source from stdin and writes the svg to stdout.
+/// the rendered svg is cached in `cache_dir` by the hash of command and source.
+#[derive(Debug, Clone, Default)]
+pub struct DiagramRenderer {
+    /// diagram language and its render command
+    commands: BTreeMap<String, String>,
+    /// svg cache directory
+    cache_dir: PathBuf,
+}
+
+impl DiagramRenderer {
+    /// create new `DiagramRenderer`, the languages of empty command are disabled.
+    pub fn new<P: Into<PathBuf>>(commands: &BTreeMap<String, String>, cache_dir: P) -> DiagramRenderer {
+        DiagramRenderer {
+            commands: commands
+                .iter()
+                .filter(|(_, cmd)| !cmd.trim().is_empty())
+                .map(|(lang, cmd)| (lang.clone(), cmd.clone()))
+                .collect(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// replace the diagram code blocks of markdown `events` with inline svg.
+    ///
+    /// the code block is kept if the diagram fails to render.
+    pub fn render_events<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        if self.commands.is_empty() {
+            return events;
+        }
+        let mut output = Vec::with_capacity(events.len());
+        let mut block: Option<(String, Vec<Event<'a>>)> = None;
+        for event in events {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if block.is_none() => {
+                    let lang = info.split_whitespace().next().unwrap_or_default();
+                    if self.commands.contains_key(lang) {
+                        block = Some((lang.to_string(), vec![event]));
+                    } else {
+                        output.push(event);
+                    }
+                }
+                Event::End(TagEnd::CodeBlock) if block.is_some() => {
+                    let (lang, mut events) = block.take().expect("diagram block error");
+                    let source: String = events
+                        .iter()
+                        .filter_map(|e| match e {
+                            Event::Text(s) => Some(s.as_ref()),
+                            _ => None,
+                        })
+                        .collect();
+                    match self.render(&lang, &source) {
+                        Some(svg) => {
+                            let html = format!("<div class=\"diagram diagram-{}\">{}</div>\n", lang, svg);
+                            output.push(Event::Html(html.into()));
+                        }
+                        None => {
+                            events.push(event);
+                            output.extend(events);
+                        }
+                    }
+                }
+                _ => match block {
+                    Some((_, ref mut events)) => events.push(event),
+                    None => output.push(event),
+                },
+            }
+        }
+        output
+    }
+
+    /// render the diagram `source` of `lang` to svg, use the cached svg if exists.
+    pub fn render(&self, lang: &str, source: &str) -> Option<String> {
+        let command = self.commands.get(lang)?;
+        let mut hasher = Sha256::new();
+        hasher.update(command.as_bytes());
+        hasher.update([0]);
+        hasher.update(source.as_bytes());
+        let hash = format!("{:x}", hasher.finalize());
+        let cache_path = self.cache_dir.join(format!("{}-{}.svg", lang, &hash[..16]));
+        if let Ok(svg) = std::fs::read_to_string(&cache_path) {
+            return Some(svg);
+        }
+
+        debug!("rendering {} diagram ...", lang);
+        let svg = match run_command(command, source) {
+            Ok(svg) => svg,
+            Err(reason) => {
+                warn!("{} diagram render error, the source is kept: {}", lang, reason);
+                return None;
+            }
+        };
+        if let Err(e) = write_file(&cache_path, svg.as_bytes()) {
+            warn!("{} diagram cache error: {}", lang, e);
+        }
+        Some(svg)
+    }
+}
+
+/// run the diagram `command` with `source` as stdin, return the svg element of stdout.
+///
+/// stdin is written from another thread, so the command streaming its output is not blocked on a full stdout pipe.
+fn run_command(command: &str, source: &str) -> std::result::Result<String, String> {
+    let mut args = command.split_whitespace();
+    let program = args.next().unwrap_or_default();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("`{}` {}", program, e))?;
+    let mut stdin = child.stdin.take().expect("diagram command stdin error");
+    let source = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let written = writer.join().expect("diagram command stdin thread error");
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    written.map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.find("<svg") {
+        Some(start) => Ok(stdout[start..].trim_end().to_string()),
+        None => Err(format!("`{}` output is not svg", program)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_streaming_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("render.sh");
+        std::fs::write(
+            &script,
+            "echo '<svg>'\nhead -c 300000 /dev/zero | tr '\\0' a\ncat\necho '</svg>'\n",
+        )
+        .unwrap();
+        let source = "b".repeat(300_000);
+        let command = format!("sh {}", script.display());
+        let svg = run_command(&command, &source).unwrap();
+        assert!(svg.starts_with("<svg>\naaa"));
+        assert!(svg.ends_with("bbb</svg>"));
+        assert_eq!(svg.len(), "<svg>\n".len() + 300_000 + 300_000 + "</svg>".len());
+    }
+
+    #[test]
+    fn run_failed_command() {
+        assert!(run_command("mdblog-missing-renderer", "digraph {}").is_err());
+        assert_eq!(run_command("cat", "no svg"), Err("`cat` output is not svg".to_string()));
+    }
+}
@@ -36,12 +36,32 @@ pub enum Error {
     /// toml export error
     #[display("toml export error")]
     TomlExport(toml::ser::Error),
+    /// zip archive error
+    #[display("zip archive error")]
+    ZipArchive(zip::result::ZipError),
+    /// json export error
+    #[display("json export error")]
+    JsonExport(serde_json::Error),
     /// path expand error
     #[display("path expand error")]
     PathExpend(shellexpand::LookupError<std::env::VarError>),
     /// post head parse error
     #[display("{:?}: post head parse error, please use yaml grammar", _1)]
     PostHeadPaser(serde_yaml::Error, PathBuf),
+    /// post toml head parse error
+    #[display("{:?}: post head parse error, please use toml grammar", _1)]
+    PostHeadTomlPaser(Box<toml::de::Error>, PathBuf),
+    /// theme config parse error
+    #[display("blog theme {:?} theme.toml parse error", _1)]
+    ThemeConfigParse(Box<toml::de::Error>, String),
+    /// theme template error, with the template file and line
+    #[from(ignore)]
+    #[display("theme template {} error", _1)]
+    ThemeTemplate(tera::Error, String),
+    /// post shortcode render error
+    #[from(ignore)]
+    #[display("{:?}:{}: post shortcode render error", _1, _2)]
+    PostShortcode(tera::Error, PathBuf, usize),
 
     /// blog root directory already exists error
     #[from(ignore)]
@@ -67,9 +87,28 @@ pub enum Error {
     #[from(ignore)]
     #[display("blog theme {:?} not found", _0)]
     ThemeNotFound(String),
+    /// blog theme already existed error
+    #[from(ignore)]
+    #[display("blog theme {:?} already existed", _0)]
+    ThemeExisted(String),
+    /// blog theme install error
+    #[from(ignore)]
+    #[display("install blog theme from {:?} error: {}", _0, _1)]
+    ThemeInstall(String, String),
+    /// blog theme inherits itself error
+    #[from(ignore)]
+    #[display("blog theme {:?} inherits itself", _0)]
+    ThemeParentCycle(String),
+    /// blog theme requires another mdblog version error
+    #[from(ignore)]
+    #[display("blog theme {:?} requires mdblog {}, the current version is {}", _0, _1, env!("CARGO_PKG_VERSION"))]
+    ThemeVersionUnsupported(String, String),
     /// post must has two parts error
     #[from(ignore)]
-    #[display("post {:?} must has two parts: headers and body, splitted by first blank line", _0)]
+    #[display(
+        "post {:?} must has two parts: headers and body, splitted by first blank line or `---`/`+++` lines",
+        _0
+    )]
     PostOnlyOnePart(PathBuf),
     /// post head part is empty error
     #[from(ignore)]
@@ -79,6 +118,30 @@ pub enum Error {
     #[from(ignore)]
     #[display("post {:?} body part is empty", _0)]
     PostNoBody(PathBuf),
+    /// post language is not declared error
+    #[from(ignore)]
+    #[display("post {:?} language {:?} is not declared in `languages` settings", _0, _1)]
+    PostLanguageUnknown(PathBuf, String),
+    /// post extra header is invalid error
+    #[from(ignore)]
+    #[display("post {:?} header {:?} {}", _0, _1, _2)]
+    PostExtraHeaderInvalid(PathBuf, String, String),
+    /// post template is not found in theme error
+    #[from(ignore)]
+    #[display("post {:?} template {:?} not found in theme", _0, _1)]
+    PostTemplateNotFound(PathBuf, String),
+    /// post internal links are unresolved error
+    #[from(ignore)]
+    #[display("unresolved internal links:\n  {}", _0.join("\n  "))]
+    PostLinkUnresolved(Vec<String>),
+    /// broken links found in generated html files error
+    #[from(ignore)]
+    #[display("{} broken links found", _0)]
+    LinksBroken(usize),
+    /// content problems found error
+    #[from(ignore)]
+    #[display("{} content errors found", _0)]
+    ContentErrors(usize),
 }
 
 impl StdError for Error {
@@ -94,17 +157,33 @@ impl StdError for Error {
             Notify(e) => Some(e),
             GlobPattern(e) => Some(e),
             TomlExport(e) => Some(e),
+            ZipArchive(e) => Some(e),
+            JsonExport(e) => Some(e),
             PathExpend(e) => Some(e),
             PostHeadPaser(e, _) => Some(e),
+            PostHeadTomlPaser(e, _) => Some(e.as_ref()),
+            ThemeConfigParse(e, _) => Some(e.as_ref()),
+            ThemeTemplate(e, _) => Some(e),
+            PostShortcode(e, _, _) => Some(e),
             RootDirExisted(_) => None,
             PostPathInvaild(_) => None,
             PostPathExisted(_) => None,
             ThemeFileEncoding(_) => None,
             ThemeInUse(_) => None,
             ThemeNotFound(_) => None,
+            ThemeExisted(_) => None,
+            ThemeInstall(..) => None,
+            ThemeParentCycle(_) => None,
+            ThemeVersionUnsupported(..) => None,
             PostOnlyOnePart(_) => None,
             PostNoHead(_) => None,
             PostNoBody(_) => None,
+            PostLanguageUnknown(..) => None,
+            PostExtraHeaderInvalid(..) => None,
+            PostTemplateNotFound(..) => None,
+            PostLinkUnresolved(_) => None,
+            LinksBroken(_) => None,
+            ContentErrors(_) => None,
         }
     }
 }
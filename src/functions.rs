@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::ImageFormat;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tera::{Tera, Value};
+
+use crate::assets::AssetManifest;
+use crate::images::{ImageProcessor, PROCESSED_IMAGES_DIR};
+use crate::utils::resolve_internal_link;
+
+/// the context of template functions
+pub struct FunctionContext {
+    /// blog root directory
+    pub root: PathBuf,
+    /// blog media root directory
+    pub media_root: PathBuf,
+    /// blog build root directory
+    pub build_root: PathBuf,
+    /// blog site url
+    pub site_url: String,
+    /// post urls keyed by post path, used by `@/` links
+    pub links: HashMap<PathBuf, String>,
+    /// image processor of `resize_image`
+    pub images: ImageProcessor,
+    /// fingerprinted theme static files of `static_asset`
+    pub assets: AssetManifest,
+}
+
+/// theme static file, returned by `static_asset`
+#[derive(Serialize)]
+struct StaticAsset {
+    url: String,
+    integrity: String,
+}
+
+/// resized image, returned by `resize_image`
+#[derive(Serialize)]
+struct ResizedImage {
+    url: String,
+    width: u32,
+    height: u32,
+}
+
+/// image metadata, returned by `image_meta`
+#[derive(Serialize)]
+struct ImageMeta {
+    width: u32,
+    height: u32,
+    format: String,
+}
+
+/// register template functions to `renderer`:
+///
+/// * `url_for(path)`: the site url of `path`, `@/posts/hello.md` is resolved to the post url
+/// * `asset(path)`: the site url of built file `path` with cache-busting hash, `/static/main.css?h=1a2b3c4d`
+/// * `resize_image(path, width, height, op)`: the resized image `{url, width, height}`
+/// * `image_meta(path)`: the image `{width, height, format}`
+/// * `static_asset(path)`: the theme static file `{url, integrity}`, fingerprinted if enabled
+pub fn register_functions(renderer: &mut Tera, context: FunctionContext) {
+    let context = Arc::new(context);
+
+    let ctx = context.clone();
+    renderer.register_function("url_for", move |args: &HashMap<String, Value>| {
+        let path = string_arg(args, "url_for", "path")?;
+        ctx.url_for(&path).map(Value::String).map_err(tera::Error::msg)
+    });
+
+    let ctx = context.clone();
+    renderer.register_function("asset", move |args: &HashMap<String, Value>| {
+        let path = string_arg(args, "asset", "path")?;
+        ctx.asset(&path).map(Value::String).map_err(tera::Error::msg)
+    });
+
+    let ctx = context.clone();
+    renderer.register_function("resize_image", move |args: &HashMap<String, Value>| {
+        let path = string_arg(args, "resize_image", "path")?;
+        let width = args.get("width").and_then(|x| x.as_u64()).map(|x| x as u32);
+        let height = args.get("height").and_then(|x| x.as_u64()).map(|x| x as u32);
+        let op = args.get("op").and_then(|x| x.as_str()).unwrap_or("fill");
+        let image = ctx.resize_image(&path, width, height, op).map_err(tera::Error::msg)?;
+        Ok(tera::to_value(image)?)
+    });
+
+    let ctx = context.clone();
+    renderer.register_function("image_meta", move |args: &HashMap<String, Value>| {
+        let path = string_arg(args, "image_meta", "path")?;
+        let meta = ctx.image_meta(&path).map_err(tera::Error::msg)?;
+        Ok(tera::to_value(meta)?)
+    });
+
+    let ctx = context;
+    renderer.register_function("static_asset", move |args: &HashMap<String, Value>| {
+        let path = string_arg(args, "static_asset", "path")?;
+        Ok(tera::to_value(ctx.static_asset(&path))?)
+    });
+}
+
+/// the required string argument `name` of function `func`.
+fn string_arg(args: &HashMap<String, Value>, func: &str, name: &str) -> tera::Result<String> {
+    match args.get(name).and_then(|x| x.as_str()) {
+        Some(value) => Ok(value.to_string()),
+        None => Err(tera::Error::msg(format!(
+            "function `{}` requires string argument `{}`",
+            func, name
+        ))),
+    }
+}
+
+impl FunctionContext {
+    fn url_for(&self, path: &str) -> Result<String, String> {
+        if path.starts_with("@/") {
+            return resolve_internal_link(path, &self.links)
+                .ok_or_else(|| format!("internal link {} is unresolved", path));
+        }
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return Ok(path.to_string());
+        }
+        Ok(format!("{}/{}", self.site_url, path.trim_start_matches('/')))
+    }
+
+    fn asset(&self, path: &str) -> Result<String, String> {
+        let rel = path.trim_start_matches('/');
+        let content = std::fs::read(self.build_root.join(rel)).map_err(|e| format!("asset {}: {}", path, e))?;
+        let hash = format!("{:x}", Sha256::digest(content));
+        Ok(format!("{}/{}?h={}", self.site_url, rel, &hash[..8]))
+    }
+
+    fn resize_image(
+        &self,
+        path: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        op: &str,
+    ) -> Result<ResizedImage, String> {
+        let src = self.source_path(path);
+        let (name, width, height) = self.images.resize(&src, width, height, op)?;
+        let dest = self.build_root.join(PROCESSED_IMAGES_DIR).join(&name);
+        if !dest.exists() {
+            std::fs::create_dir_all(self.build_root.join(PROCESSED_IMAGES_DIR)).map_err(|e| e.to_string())?;
+            std::fs::copy(self.images.cache_path(&name), &dest).map_err(|e| e.to_string())?;
+        }
+        Ok(ResizedImage {
+            url: self.images.url(&name),
+            width,
+            height,
+        })
+    }
+
+    fn image_meta(&self, path: &str) -> Result<ImageMeta, String> {
+        let src = self.source_path(path);
+        let (width, height) = image::image_dimensions(&src).map_err(|e| format!("image {}: {}", path, e))?;
+        let format = ImageFormat::from_path(&src)
+            .ok()
+            .and_then(|x| x.extensions_str().first())
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        Ok(ImageMeta { width, height, format })
+    }
+
+    fn static_asset(&self, path: &str) -> StaticAsset {
+        let rel = path.trim_start_matches('/');
+        match self.assets.get(rel) {
+            Some(asset) => StaticAsset {
+                url: format!("{}/{}", self.site_url, asset.path),
+                integrity: asset.integrity.clone(),
+            },
+            None => StaticAsset {
+                url: format!("{}/{}", self.site_url, rel),
+                integrity: String::new(),
+            },
+        }
+    }
+
+    /// the source file of `path`, `/media/` path is in the media directory, others are relative to blog root.
+    fn source_path(&self, path: &str) -> PathBuf {
+        let path = path.strip_prefix(&self.site_url).unwrap_or(path);
+        let path = path.trim_start_matches('/');
+        match path.strip_prefix("media/") {
+            Some(media) => self.media_root.join(media),
+            None => self.root.join(path),
+        }
+    }
+}
@@ -24,6 +24,7 @@ impl HttpServer {
         HttpServer { host, port, root_dir }
     }
 
+    #[allow(clippy::let_unit_value)]
     pub fn run(&self) {
         let host = self.host.clone();
         let port = self.port;
@@ -46,8 +47,8 @@ impl HttpServer {
                 .unwrap();
             rt.block_on(async move {
                 let listener = tokio::net::TcpListener::bind((host, port)).await.unwrap();
-                axum::serve(listener, app).await.unwrap();
                 server_tx.send(()).unwrap();
+                axum::serve(listener, app).await.unwrap();
             });
         });
         _ = server_rx.recv().unwrap();
@@ -0,0 +1,336 @@
+use std::collections::BTreeSet;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::error::Result;
+use crate::linkcheck::attr_values;
+use crate::settings::ImageSettings;
+use crate::utils::percent_encode;
+
+/// the directory of processed images in build directory
+pub const PROCESSED_IMAGES_DIR: &str = "processed_images";
+
+/// the image formats can be encoded
+const ENCODABLE_FORMATS: &[&str] = &["webp"];
+
+/// responsive image processor of post content
+///
+/// the `<img>` tags of local jpeg, png and webp images are rewritten with `srcset`, `sizes`,
+/// `width`, `height` and `loading="lazy"` attributes, wrapped in `<picture>` with modern format sources.
+/// the resized variants are cached in `cache_dir` and exported to `processed_images` directory.
+#[derive(Debug, Clone, Default)]
+pub struct ImageProcessor {
+    /// image settings
+    settings: ImageSettings,
+    /// modern formats of variants
+    formats: Vec<String>,
+    /// variants cache directory
+    cache_dir: PathBuf,
+    /// blog site url
+    site_url: String,
+    /// processed variant file names
+    variants: BTreeSet<String>,
+}
+
+impl ImageProcessor {
+    /// create new `ImageProcessor`
+    pub fn new<P: Into<PathBuf>>(settings: &ImageSettings, cache_dir: P, site_url: &str) -> ImageProcessor {
+        let mut formats = Vec::new();
+        for format in &settings.formats {
+            let format = format.to_lowercase();
+            if ENCODABLE_FORMATS.contains(&format.as_str()) {
+                formats.push(format);
+            } else {
+                warn!("image format {} is not encodable, skipped", format);
+            }
+        }
+        ImageProcessor {
+            settings: settings.clone(),
+            formats,
+            cache_dir: cache_dir.into(),
+            site_url: site_url.trim_end_matches('/').to_string(),
+            variants: BTreeSet::new(),
+        }
+    }
+
+    /// whether the processor is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enable
+    }
+
+    /// rewrite the `<img>` tags of html `content`, `resolve` maps the image `src` to the local file.
+    pub fn rewrite(&mut self, content: &str, resolve: &dyn Fn(&str) -> Option<PathBuf>) -> String {
+        if !self.settings.enable || !content.contains("<img ") {
+            return content.to_string();
+        }
+        let mut output = String::with_capacity(content.len());
+        let mut pos = 0;
+        while let Some(found) = content[pos..].find("<img ") {
+            let start = pos + found;
+            let end = content[start..]
+                .find('>')
+                .map(|i| start + i + 1)
+                .unwrap_or(content.len());
+            output.push_str(&content[pos..start]);
+            let tag = &content[start..end];
+            let picture = attr_values(tag, &["src"])
+                .pop()
+                .and_then(|src| resolve(&src))
+                .and_then(|path| self.picture(tag, &path));
+            match picture {
+                Some(picture) => output.push_str(&picture),
+                None => output.push_str(tag),
+            }
+            pos = end;
+        }
+        output.push_str(&content[pos..]);
+        output
+    }
+
+    /// the responsive `<picture>` html of `<img>` tag of local image `path`.
+    fn picture(&mut self, tag: &str, path: &Path) -> Option<String> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        if !["jpg", "jpeg", "png", "webp"].contains(&ext.as_str()) {
+            return None;
+        }
+        let (width, height) = match image::image_dimensions(path) {
+            Ok(dimensions) => dimensions,
+            Err(e) => {
+                warn!("image {} read error: {}", path.display(), e);
+                return None;
+            }
+        };
+        let mut widths: Vec<u32> = self.settings.widths.iter().copied().filter(|w| *w < width).collect();
+        widths.sort_unstable();
+        widths.dedup();
+
+        let mut source = None;
+        let mut srcset = Vec::new();
+        for w in &widths {
+            let url = self.variant(path, &mut source, *w, &ext)?;
+            srcset.push(format!("{} {}w", url, w));
+        }
+        if !srcset.is_empty() {
+            let src = attr_values(tag, &["src"]).pop().unwrap_or_default();
+            srcset.push(format!("{} {}w", src, width));
+        }
+        let mut sources = Vec::new();
+        for format in self.formats.clone() {
+            if format == ext {
+                continue;
+            }
+            let mut set = Vec::new();
+            for w in widths.iter().chain(std::iter::once(&width)) {
+                let url = self.variant(path, &mut source, *w, &format)?;
+                set.push(format!("{} {}w", url, w));
+            }
+            sources.push(format!(
+                r#"<source type="image/{}" srcset="{}" sizes="{}">"#,
+                format,
+                set.join(", "),
+                self.settings.sizes
+            ));
+        }
+
+        let mut attrs = String::new();
+        if !srcset.is_empty() && !has_attr(tag, "srcset") {
+            attrs.push_str(&format!(
+                r#" srcset="{}" sizes="{}""#,
+                srcset.join(", "),
+                self.settings.sizes
+            ));
+        }
+        if !has_attr(tag, "width") && !has_attr(tag, "height") {
+            attrs.push_str(&format!(r#" width="{}" height="{}""#, width, height));
+        }
+        if !has_attr(tag, "loading") {
+            attrs.push_str(r#" loading="lazy""#);
+        }
+        let head = tag.trim_end_matches('>').trim_end_matches('/').trim_end();
+        let img = format!("{}{} />", head, attrs);
+        if sources.is_empty() {
+            Some(img)
+        } else {
+            Some(format!("<picture>{}{}</picture>", sources.join(""), img))
+        }
+    }
+
+    /// the url of `width` and `format` variant of image `path`, the variant is generated if not cached.
+    fn variant(&mut self, path: &Path, source: &mut Option<DynamicImage>, width: u32, format: &str) -> Option<String> {
+        let name = self.cache_name(path, &width.to_string(), format)?;
+        let cache_path = self.cache_dir.join(&name);
+        if !cache_path.exists() {
+            if source.is_none() {
+                debug!("processing image: {}", path.display());
+                *source = match image::open(path) {
+                    Ok(img) => Some(img),
+                    Err(e) => {
+                        warn!("image {} decode error: {}", path.display(), e);
+                        return None;
+                    }
+                };
+            }
+            let img = source.as_ref()?;
+            let resized = if width < img.width() {
+                img.resize(width, u32::MAX, FilterType::Lanczos3)
+            } else {
+                img.clone()
+            };
+            if let Err(e) = self.encode(&resized, &cache_path, format) {
+                warn!("image {} encode error: {}", path.display(), e);
+                return None;
+            }
+        }
+        self.variants.insert(name.clone());
+        Some(self.url(&name))
+    }
+
+    /// resize image `path` with `op`, return the file name in cache directory, the width and height.
+    ///
+    /// the ops are:
+    /// * `scale`: resize to `width` x `height`, the aspect ratio is not kept
+    /// * `fit_width`: resize to `width`, the aspect ratio is kept
+    /// * `fit_height`: resize to `height`, the aspect ratio is kept
+    /// * `fit`: resize to fit in `width` x `height` box, the aspect ratio is kept, not upscaled
+    /// * `fill`: resize and crop to `width` x `height`, the aspect ratio is kept
+    pub fn resize(
+        &self,
+        path: &Path,
+        width: Option<u32>,
+        height: Option<u32>,
+        op: &str,
+    ) -> std::result::Result<(String, u32, u32), String> {
+        let ext = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_lowercase())
+            .unwrap_or_default();
+        if !["jpg", "jpeg", "png", "webp"].contains(&ext.as_str()) {
+            return Err(format!("image {} is not jpeg, png or webp", path.display()));
+        }
+        let (w, h) = image::image_dimensions(path).map_err(|e| format!("image {}: {}", path.display(), e))?;
+        let required = |x: Option<u32>, name: &str| {
+            x.filter(|x| *x > 0)
+                .ok_or(format!("`{}` is required by `{}` op", name, op))
+        };
+        let (new_w, new_h) = match op {
+            "scale" | "fill" => (required(width, "width")?, required(height, "height")?),
+            "fit_width" => {
+                let new_w = required(width, "width")?;
+                (new_w, ((h as f64 * new_w as f64 / w as f64).round() as u32).max(1))
+            }
+            "fit_height" => {
+                let new_h = required(height, "height")?;
+                (((w as f64 * new_h as f64 / h as f64).round() as u32).max(1), new_h)
+            }
+            "fit" => {
+                let (box_w, box_h) = (required(width, "width")?, required(height, "height")?);
+                let ratio = (box_w as f64 / w as f64).min(box_h as f64 / h as f64).min(1.0);
+                (
+                    ((w as f64 * ratio).round() as u32).max(1),
+                    ((h as f64 * ratio).round() as u32).max(1),
+                )
+            }
+            _ => return Err(format!("unknown resize op `{}`", op)),
+        };
+
+        let suffix = format!("{}-{}x{}", op, new_w, new_h);
+        let name = self
+            .cache_name(path, &suffix, &ext)
+            .ok_or(format!("image {} not found", path.display()))?;
+        let cache_path = self.cache_dir.join(&name);
+        if !cache_path.exists() {
+            debug!("resizing image: {}", path.display());
+            let img = image::open(path).map_err(|e| format!("image {}: {}", path.display(), e))?;
+            let resized = if op == "fill" {
+                img.resize_to_fill(new_w, new_h, FilterType::Lanczos3)
+            } else if (new_w, new_h) == (w, h) {
+                img
+            } else {
+                img.resize_exact(new_w, new_h, FilterType::Lanczos3)
+            };
+            self.encode(&resized, &cache_path, &ext)?;
+        }
+        Ok((name, new_w, new_h))
+    }
+
+    /// the cache file name of image `path` variant, the name is changed when the image is modified.
+    fn cache_name(&self, path: &Path, suffix: &str, format: &str) -> Option<String> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(meta.len().to_le_bytes());
+        if let Ok(modified) = meta.modified() {
+            let modified = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+            hasher.update(modified.as_secs().to_le_bytes());
+            hasher.update(modified.subsec_nanos().to_le_bytes());
+        }
+        hasher.update([self.settings.quality]);
+        let hash = format!("{:x}", hasher.finalize());
+        let stem = path.file_stem()?.to_str()?;
+        Some(format!("{}.{}.{}.{}", stem, &hash[..16], suffix, format))
+    }
+
+    /// the url of processed image `name`.
+    pub fn url(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.site_url, PROCESSED_IMAGES_DIR, percent_encode(name))
+    }
+
+    /// the cache path of processed image `name`.
+    pub fn cache_path(&self, name: &str) -> PathBuf {
+        self.cache_dir.join(name)
+    }
+
+    /// encode `img` to `path` in `format`.
+    fn encode(&self, img: &DynamicImage, path: &Path, format: &str) -> std::result::Result<(), String> {
+        if let Some(p) = path.parent() {
+            std::fs::create_dir_all(p).map_err(|e| e.to_string())?;
+        }
+        let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        let mut writer = BufWriter::new(file);
+        let result = match format {
+            "jpg" | "jpeg" => {
+                let encoder = JpegEncoder::new_with_quality(&mut writer, self.settings.quality);
+                DynamicImage::ImageRgb8(img.to_rgb8()).write_with_encoder(encoder)
+            }
+            "png" => img.write_to(&mut writer, ImageFormat::Png),
+            "webp" if img.color().has_alpha() => {
+                DynamicImage::ImageRgba8(img.to_rgba8()).write_to(&mut writer, ImageFormat::WebP)
+            }
+            "webp" => DynamicImage::ImageRgb8(img.to_rgb8()).write_to(&mut writer, ImageFormat::WebP),
+            _ => return Err(format!("unsupported format {}", format)),
+        };
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(path);
+            return Err(e.to_string());
+        }
+        Ok(())
+    }
+
+    /// export the processed variants to `processed_images` directory of `build_dir`.
+    pub fn export<P: AsRef<Path>>(&self, build_dir: P) -> Result<()> {
+        let dest_dir = build_dir.as_ref().join(PROCESSED_IMAGES_DIR);
+        for name in &self.variants {
+            let dest = dest_dir.join(name);
+            if dest.exists() {
+                continue;
+            }
+            std::fs::create_dir_all(&dest_dir)?;
+            std::fs::copy(self.cache_dir.join(name), dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// check the `<img>` tag has attribute `name`.
+fn has_attr(tag: &str, name: &str) -> bool {
+    !attr_values(tag, &[name]).is_empty()
+}
@@ -0,0 +1,291 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use flate2::read::GzDecoder;
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+/// theme source of `mdblog theme install`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeSource {
+    /// `.tar.gz` or `.tgz` archive
+    TarGz,
+    /// `.zip` archive
+    Zip,
+    /// git repository url or path
+    Git,
+}
+
+impl ThemeSource {
+    /// detect the source kind by file extension, other sources are git repositories.
+    pub fn detect(source: &str) -> ThemeSource {
+        let lower = source.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            ThemeSource::TarGz
+        } else if lower.ends_with(".zip") {
+            ThemeSource::Zip
+        } else {
+            ThemeSource::Git
+        }
+    }
+}
+
+/// the default theme name of `source`, the file or repository name without extension.
+pub fn source_name(source: &str) -> String {
+    let source = source.trim_end_matches(['/', '\\']);
+    let name = source.rsplit(['/', '\\', ':']).next().unwrap_or(source);
+    let lower = name.to_lowercase();
+    for ext in [".tar.gz", ".tgz", ".zip", ".git"] {
+        if lower.ends_with(ext) {
+            return name[..name.len() - ext.len()].to_string();
+        }
+    }
+    name.to_string()
+}
+
+/// fetch the theme `source` into the empty `dest` directory, return the theme directory in it.
+///
+/// the archive is extracted and the git repository is cloned,
+/// the theme directory is the only top-level directory if the theme files are not at the top level.
+pub fn fetch_theme(source: &str, dest: &Path) -> Result<PathBuf> {
+    match ThemeSource::detect(source) {
+        ThemeSource::TarGz => {
+            debug!("extracting theme archive {} ...", source);
+            let mut archive = tar::Archive::new(GzDecoder::new(open_archive(source)?));
+            archive.unpack(dest)?;
+        }
+        ThemeSource::Zip => {
+            debug!("extracting theme archive {} ...", source);
+            let mut archive = zip::ZipArchive::new(open_archive(source)?)?;
+            std::fs::create_dir_all(dest)?;
+            archive.extract(dest)?;
+        }
+        ThemeSource::Git => {
+            debug!("cloning theme repository {} ...", source);
+            let output = Command::new("git")
+                .args(["clone", "--depth", "1", "--quiet", "--", source])
+                .arg(dest)
+                .output()
+                .map_err(|e| Error::ThemeInstall(source.into(), format!("`git` {}", e)))?;
+            if !output.status.success() {
+                let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                return Err(Error::ThemeInstall(source.into(), reason));
+            }
+            std::fs::remove_dir_all(dest.join(".git"))?;
+        }
+    }
+    theme_dir(source, dest)
+}
+
+/// open the archive file of `source`.
+fn open_archive(source: &str) -> Result<File> {
+    File::open(source).map_err(|e| Error::ThemeInstall(source.into(), e.to_string()))
+}
+
+/// the theme directory of fetched `dir`.
+fn theme_dir(source: &str, dir: &Path) -> Result<PathBuf> {
+    if is_theme_dir(dir) {
+        return Ok(dir.to_owned());
+    }
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path.file_name().and_then(|x| x.to_str()).map(|x| x.starts_with('.'));
+        if hidden == Some(false) {
+            dirs.push(path);
+        }
+    }
+    match dirs.pop() {
+        Some(path) if dirs.is_empty() && path.is_dir() && is_theme_dir(&path) => Ok(path),
+        _ => Err(Error::ThemeInstall(
+            source.into(),
+            "no `theme.toml` or `templates` directory found".to_string(),
+        )),
+    }
+}
+
+/// check `dir` has the theme files.
+fn is_theme_dir(dir: &Path) -> bool {
+    dir.join("theme.toml").is_file() || dir.join("templates").is_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::Mdblog;
+
+    const FILES: &[(&str, &str)] = &[
+        ("theme.toml", "name = \"fancy\"\n"),
+        ("templates/post.tpl", "{% extends \"base.tpl\" %}\n"),
+        ("static/main.css", "body {}\n"),
+    ];
+
+    /// write `files` to a tar.gz archive `path`, every file is under `prefix`.
+    fn tar_gz(path: &Path, prefix: &str, files: &[(&str, &str)]) {
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(path).unwrap(), Compression::default()));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("{}{}", prefix, name), content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    /// write `files` to a zip archive `path`, every file is under `prefix`.
+    fn zip(path: &Path, prefix: &str, files: &[(&str, &str)]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in files {
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file(format!("{}{}", prefix, name), options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    /// create a bare git repository `<dir>/fancy-theme.git` of `files`.
+    fn git_repo(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let work = dir.join("work");
+        for (name, content) in files {
+            let path = work.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let git = |dir: &Path, args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&work, &["init", "--quiet"]);
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "theme"]);
+        git(dir, &["clone", "--bare", "--quiet", "work", "fancy-theme.git"]);
+        dir.join("fancy-theme.git")
+    }
+
+    fn assert_theme(dir: &Path) {
+        for (name, content) in FILES {
+            assert_eq!(std::fs::read_to_string(dir.join(name)).unwrap(), *content, "{}", name);
+        }
+    }
+
+    #[test]
+    fn detect_source() {
+        assert_eq!(ThemeSource::detect("a/fancy.tar.gz"), ThemeSource::TarGz);
+        assert_eq!(ThemeSource::detect("fancy.TGZ"), ThemeSource::TarGz);
+        assert_eq!(ThemeSource::detect("fancy.zip"), ThemeSource::Zip);
+        assert_eq!(ThemeSource::detect("https://example.com/fancy.git"), ThemeSource::Git);
+        assert_eq!(source_name("a/fancy-1.0.tar.gz"), "fancy-1.0");
+        assert_eq!(source_name("https://example.com/user/fancy.git/"), "fancy");
+        assert_eq!(source_name("git@example.com:fancy"), "fancy");
+    }
+
+    #[test]
+    fn fetch_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        for prefix in ["", "fancy-1.0/"] {
+            let tar_path = dir.path().join(format!("{}.tar.gz", prefix.len()));
+            tar_gz(&tar_path, prefix, FILES);
+            let dest = dir.path().join(format!("tar-{}", prefix.len()));
+            let fetched = fetch_theme(tar_path.to_str().unwrap(), &dest).unwrap();
+            assert_eq!(fetched, dest.join(prefix));
+            assert_theme(&fetched);
+
+            let zip_path = dir.path().join(format!("{}.zip", prefix.len()));
+            zip(&zip_path, prefix, FILES);
+            let dest = dir.path().join(format!("zip-{}", prefix.len()));
+            let fetched = fetch_theme(zip_path.to_str().unwrap(), &dest).unwrap();
+            assert_eq!(fetched, dest.join(prefix));
+            assert_theme(&fetched);
+        }
+    }
+
+    #[test]
+    fn fetch_git_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git_repo(dir.path(), FILES);
+        let dest = dir.path().join("fetched");
+        let fetched = fetch_theme(repo.to_str().unwrap(), &dest).unwrap();
+        assert_eq!(fetched, dest);
+        assert_theme(&fetched);
+        assert!(!fetched.join(".git").exists());
+    }
+
+    #[test]
+    fn fetch_invalid_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("readme.tar.gz");
+        tar_gz(&archive, "", &[("a/README", "a"), ("b/README", "b")]);
+        let result = fetch_theme(archive.to_str().unwrap(), &dir.path().join("a"));
+        assert!(matches!(result, Err(Error::ThemeInstall(..))));
+
+        let missing = dir.path().join("missing.zip");
+        let result = fetch_theme(missing.to_str().unwrap(), &dir.path().join("b"));
+        assert!(matches!(result, Err(Error::ThemeInstall(..))));
+    }
+
+    #[test]
+    fn install_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let blog = Mdblog::new(dir.path().join("blog")).unwrap();
+        let themes = dir.path().join("blog/_themes");
+
+        let archive = dir.path().join("fancy-1.0.tar.gz");
+        tar_gz(&archive, "fancy-1.0/", FILES);
+        blog.install_blog_theme(archive.to_str().unwrap(), None, false).unwrap();
+        assert_theme(&themes.join("fancy"));
+
+        let repo = git_repo(dir.path(), FILES);
+        blog.install_blog_theme(repo.to_str().unwrap(), Some("other"), false)
+            .unwrap();
+        assert_theme(&themes.join("other"));
+
+        let archive = dir.path().join("noname.zip");
+        zip(&archive, "", &FILES[1..]);
+        blog.install_blog_theme(archive.to_str().unwrap(), None, false).unwrap();
+        assert!(themes.join("noname/templates/post.tpl").is_file());
+
+        let entries = std::fs::read_dir(&themes).unwrap().count();
+        assert_eq!(entries, 3, "staging directory is not removed");
+    }
+
+    #[test]
+    fn install_existing_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let blog = Mdblog::new(dir.path().join("blog")).unwrap();
+        let themes = dir.path().join("blog/_themes");
+        let archive = dir.path().join("fancy.zip");
+        zip(&archive, "", FILES);
+        blog.install_blog_theme(archive.to_str().unwrap(), None, false).unwrap();
+
+        let updated = dir.path().join("updated.zip");
+        let files = [FILES[0], FILES[1], ("static/main.css", "body { color: red; }\n")];
+        zip(&updated, "", &files);
+        let result = blog.install_blog_theme(updated.to_str().unwrap(), None, false);
+        assert!(matches!(result, Err(Error::ThemeExisted(ref name)) if name == "fancy"));
+        assert_theme(&themes.join("fancy"));
+
+        let broken = dir.path().join("broken.zip");
+        zip(&broken, "", &[FILES[0], ("templates/post.tpl", "{% if %}\n")]);
+        assert!(blog.install_blog_theme(broken.to_str().unwrap(), None, true).is_err());
+        assert_theme(&themes.join("fancy"));
+
+        blog.install_blog_theme(updated.to_str().unwrap(), None, true).unwrap();
+        let css = std::fs::read_to_string(themes.join("fancy/static/main.css")).unwrap();
+        assert_eq!(css, "body { color: red; }\n");
+    }
+}
@@ -0,0 +1,115 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::page::Page;
+use crate::post::Post;
+use crate::settings::TaxonomySettings;
+use crate::taxonomy::Taxonomy;
+
+/// blog statistics of visible posts
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    /// the number of posts
+    pub posts: usize,
+    /// the total word count
+    pub words: usize,
+    /// the total reading time in minutes
+    pub reading_time: usize,
+}
+
+impl Stats {
+    /// add the visible posts of `posts` to statistics.
+    pub fn add(&mut self, posts: &[Rc<Post>]) {
+        for post in posts.iter().filter(|p| !p.headers.hidden) {
+            self.posts += 1;
+            self.words += post.word_count;
+            self.reading_time += post.reading_time;
+        }
+    }
+}
+
+/// blog language
+///
+/// posts of every language have their own index pages, taxonomies and feed,
+/// the default language is located at the site root, others under `/<lang>/`.
+pub struct Language {
+    /// language code, such as `en`, `zh`
+    pub code: String,
+    /// url prefix directory, empty for the default language
+    pub prefix: String,
+    /// language posts
+    pub posts: Vec<Rc<Post>>,
+    /// collection of language index pages
+    pub index_pages: Vec<Rc<Page>>,
+    /// language taxonomies map
+    pub taxonomies: BTreeMap<String, Taxonomy>,
+}
+
+impl Language {
+    /// create new `Language`
+    pub fn new(code: &str, prefix: &str, taxonomies: &[TaxonomySettings]) -> Language {
+        Language {
+            code: code.to_string(),
+            prefix: prefix.to_string(),
+            posts: Vec::new(),
+            index_pages: Vec::new(),
+            taxonomies: taxonomies
+                .iter()
+                .map(|settings| (settings.name.clone(), Taxonomy::new(settings)))
+                .collect(),
+        }
+    }
+
+    /// add a post to `Language`, hidden post is not added to taxonomies.
+    pub fn add(&mut self, post: Rc<Post>) {
+        self.posts.push(post.clone());
+        if post.headers.hidden {
+            return;
+        }
+        for taxonomy in self.taxonomies.values_mut() {
+            for term in post.terms(taxonomy.name()) {
+                taxonomy.add(term, post.clone());
+            }
+        }
+    }
+
+    /// sort language posts by created time, newest first.
+    pub fn sort_posts(&mut self) {
+        self.posts.sort_by_key(|p| Reverse(p.headers.created));
+        for taxonomy in self.taxonomies.values_mut() {
+            taxonomy.sort_posts();
+        }
+    }
+
+    /// build index pages
+    pub fn build_index_pages(&mut self, posts_per_page: usize) {
+        let posts: Vec<_> = self.posts.iter().filter(|p| !p.headers.hidden).cloned().collect();
+        self.index_pages = Page::paginate("index", &posts, posts_per_page);
+    }
+
+    /// build taxonomy term pages
+    pub fn build_taxonomy_pages(&mut self, posts_per_page: usize) {
+        for taxonomy in self.taxonomies.values_mut() {
+            taxonomy.build_pages(posts_per_page);
+        }
+    }
+
+    /// statistics of language visible posts
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        stats.add(&self.posts);
+        stats
+    }
+
+    /// the url of language root, `site_url` for the default language, `site_url/<lang>` for others.
+    pub fn url(&self, site_url: &str) -> String {
+        if self.prefix.is_empty() {
+            site_url.to_string()
+        } else {
+            format!("{}/{}", site_url, self.prefix)
+        }
+    }
+}
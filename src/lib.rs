@@ -11,7 +11,8 @@
 #![allow(clippy::expect_fun_call)]
 #![allow(clippy::or_fun_call)]
 
-use std::collections::BTreeMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::mpsc;
@@ -25,28 +26,54 @@ use notify_debouncer_mini::new_debouncer;
 use tempfile::{Builder as TempBuilder, TempDir};
 use tera::{Context, Tera};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use walkdir::{DirEntry, WalkDir};
 
+pub use crate::assets::{Asset, AssetManifest};
+pub use crate::author::Author;
+pub use crate::diagram::DiagramRenderer;
 pub use crate::error::{Error, Result};
+use crate::functions::{register_functions, FunctionContext};
 use crate::http::HttpServer;
+pub use crate::images::ImageProcessor;
+use crate::install::{fetch_theme, source_name};
+pub use crate::language::{Language, Stats};
+pub use crate::linkcheck::{BrokenLink, LinkChecker};
+pub use crate::lint::{Level, Problem};
 pub use crate::page::Page;
 pub use crate::post::Post;
-pub use crate::post::PostHeaders;
-pub use crate::settings::Settings;
+pub use crate::post::{PostHeaders, Translation};
+pub use crate::settings::{ImageSettings, LanguageSettings, Settings, TaxonomySettings};
 pub use crate::tag::Tag;
-pub use crate::theme::Theme;
-use crate::utils::write_file;
-
+pub use crate::taxonomy::Taxonomy;
+pub use crate::theme::{ExtraHeader, ExtraHeaderType, Theme, ThemeManifest};
+pub use crate::toc::TocItem;
+use crate::utils::{is_hidden, percent_encode, write_file};
+
+mod assets;
+mod author;
+mod diagram;
 mod error;
+mod functions;
 mod http;
+mod images;
+mod install;
+mod language;
+mod linkcheck;
+mod lint;
 mod page;
 mod post;
 mod settings;
+mod shortcode;
 mod tag;
+mod taxonomy;
 mod theme;
+mod toc;
 mod utils;
 
+/// the loaded post with its header and language errors, or the error of loading it.
+type PostResult = Result<(Post, Vec<Error>)>;
+
 /// blog object
 pub struct Mdblog {
     /// blog root path
@@ -57,12 +84,12 @@ pub struct Mdblog {
     theme: Theme,
     /// collection of blog posts
     posts: Vec<Rc<Post>>,
-    /// collection of blog index pages
-    index_pages: Vec<Rc<Page>>,
-    /// collection of blog tags pages
-    tag_pages: BTreeMap<String, Vec<Rc<Page>>>,
-    /// tags map
-    tags_map: BTreeMap<String, Tag>,
+    /// blog languages, the default language is the first one
+    languages: Vec<Language>,
+    /// responsive image processor of posts
+    images: ImageProcessor,
+    /// fingerprinted theme static files
+    assets: AssetManifest,
     /// server root dir
     server_root_dir: Option<TempDir>,
 }
@@ -79,9 +106,9 @@ impl Mdblog {
             settings,
             theme,
             posts: Vec::new(),
-            index_pages: Vec::new(),
-            tag_pages: BTreeMap::new(),
-            tags_map: BTreeMap::new(),
+            languages: Vec::new(),
+            images: ImageProcessor::default(),
+            assets: AssetManifest::default(),
             server_root_dir: None,
         })
     }
@@ -93,6 +120,14 @@ impl Mdblog {
     /// * `config.toml`
     /// * `BLOG_` prefix environment variable
     pub fn load_customize_settings(&mut self) -> Result<()> {
+        self.load_settings()?;
+        let theme_root_dir = self.theme_root_dir()?;
+        self.theme = Theme::new(&theme_root_dir, &self.settings.theme)?;
+        Ok(())
+    }
+
+    /// load blog settings of `config.toml` and environment variables.
+    fn load_settings(&mut self) -> Result<()> {
         let settings = Config::builder()
             .add_source(self.settings.clone())
             .add_source(config::File::with_name("config.toml"))
@@ -102,8 +137,12 @@ impl Mdblog {
         if self.settings.site_url.ends_with('/') {
             self.settings.site_url = self.settings.site_url.trim_end_matches('/').to_string();
         }
-        let theme_root_dir = self.theme_root_dir()?;
-        self.theme = Theme::new(&theme_root_dir, &self.settings.theme)?;
+        for taxonomy in &mut self.settings.taxonomies {
+            taxonomy.path = taxonomy.path.trim_matches('/').to_string();
+            if taxonomy.path.is_empty() {
+                taxonomy.path = taxonomy.name.clone();
+            }
+        }
         Ok(())
     }
 
@@ -111,10 +150,13 @@ impl Mdblog {
         self.settings.site_url = format!("http://{}:{}", host, port);
     }
 
-    /// load blog posts.
-    pub fn load_posts(&mut self) -> Result<()> {
-        let mut posts: Vec<Rc<Post>> = Vec::new();
-        let mut tags_map: BTreeMap<String, Tag> = BTreeMap::new();
+    /// read the markdown files of post directory, the posts are localized but not rendered.
+    ///
+    /// every markdown file gives its path and its `PostResult`.
+    fn read_posts(&self) -> Result<Vec<(PathBuf, PostResult)>> {
+        let mut posts = Vec::new();
+        let default_language = &self.settings.default_language;
+        let languages: Vec<&str> = self.settings.languages.keys().map(|x| x.as_str()).collect();
         let walker = WalkDir::new(&self.post_root_dir()?).into_iter();
 
         for entry in walker.filter_entry(|e| !is_hidden(e)) {
@@ -123,64 +165,161 @@ impl Mdblog {
                 continue;
             }
             let post_path = entry.path().strip_prefix(&self.root)?.to_owned();
-            let post = Post::new(&self.root, &post_path)?;
-            let post = Rc::new(post);
-            posts.push(Rc::clone(&post));
-            if post.headers.hidden {
-                continue;
+            let mut post = match Post::new(&self.root, &post_path, &languages, default_language) {
+                Ok(post) => post,
+                Err(e) => {
+                    posts.push((post_path, Err(e)));
+                    continue;
+                }
+            };
+            let mut errors = Vec::new();
+            if let Err(e) = self.theme.check_headers(&post) {
+                errors.push(e);
+            }
+            post.localize(&languages, default_language);
+            if &post.lang != default_language && !languages.contains(&post.lang.as_str()) {
+                errors.push(Error::PostLanguageUnknown(post_path.clone(), post.lang.clone()));
             }
-            for name in &post.headers.tags {
-                let tag = tags_map.entry(name.to_string()).or_insert(Tag::new(name));
-                tag.add(post.clone());
+            posts.push((post_path, Ok((post, errors))));
+        }
+        Ok(posts)
+    }
+
+    /// load blog posts.
+    pub fn load_posts(&mut self) -> Result<()> {
+        let mut posts: Vec<Post> = Vec::new();
+        let diagrams = DiagramRenderer::new(&self.settings.diagrams, self.cache_root_dir()?.join("diagrams"));
+        for (_, post) in self.read_posts()? {
+            let (mut post, errors) = post?;
+            if let Some(e) = errors.into_iter().next() {
+                return Err(e);
             }
+            post.authors = post.headers.authors.iter().map(|id| self.author(id)).collect();
+            posts.push(post);
+        }
+
+        let links = post_links(posts.iter(), &self.settings.site_url);
+        let mut unresolved = Vec::new();
+        for post in &mut posts {
+            for link in post.render(&self.settings, &self.theme, &diagrams, &links)? {
+                unresolved.push(format!("{}: {}", post.path.display(), link));
+            }
+        }
+        if !unresolved.is_empty() {
+            return Err(Error::PostLinkUnresolved(unresolved));
+        }
+        let mut images = ImageProcessor::new(
+            &self.settings.images,
+            self.cache_root_dir()?.join("images"),
+            &self.settings.site_url,
+        );
+        if images.is_enabled() {
+            let media_root = self.media_root_dir()?;
+            for post in &mut posts {
+                let resolve = |src: &str| post.local_path(src, &self.settings.site_url, &media_root);
+                let content = images.rewrite(&post.content, &resolve);
+                let summary = images.rewrite(&post.summary, &resolve);
+                post.content = content;
+                post.summary = summary;
+            }
+        }
+        self.images = images;
+
+        let mut translations: HashMap<PathBuf, Vec<Translation>> = HashMap::new();
+        for post in &posts {
+            translations
+                .entry(post.translation_key().to_owned())
+                .or_default()
+                .push(Translation {
+                    lang: post.lang.clone(),
+                    title: post.title.clone(),
+                    url: post.url.clone(),
+                });
+        }
+        for post in &mut posts {
+            if let Some(translations) = translations.get(post.translation_key()) {
+                post.translations = translations.iter().filter(|t| t.lang != post.lang).cloned().collect();
+                post.translations.sort_by(|t1, t2| t1.lang.cmp(&t2.lang));
+            }
+        }
+
+        let default_language = &self.settings.default_language;
+        let taxonomies = self.taxonomy_settings();
+        let mut blog_languages = vec![Language::new(default_language, "", &taxonomies)];
+        for lang in self.settings.languages.keys().filter(|x| *x != default_language) {
+            blog_languages.push(Language::new(lang, lang, &taxonomies));
         }
-        posts.sort_by(|p1, p2| p2.headers.created.cmp(&p1.headers.created));
-        for tag in tags_map.values_mut() {
-            tag.posts.sort_by(|p1, p2| p2.headers.created.cmp(&p1.headers.created));
+        let posts: Vec<Rc<Post>> = posts.into_iter().map(Rc::new).collect();
+        for post in &posts {
+            let language = blog_languages
+                .iter_mut()
+                .find(|x| x.code == post.lang)
+                .expect("post language missing");
+            language.add(post.clone());
+        }
+        for language in &mut blog_languages {
+            language.sort_posts();
         }
         self.posts = posts;
-        self.tags_map = tags_map;
+        self.posts.sort_by_key(|p| Reverse(p.headers.created));
+        self.languages = blog_languages;
         Ok(())
     }
 
+    /// register template functions of theme renderer, such as `url_for` and `resize_image`.
+    fn register_template_functions(&mut self) -> Result<()> {
+        let context = FunctionContext {
+            root: self.root.clone(),
+            media_root: self.media_root_dir()?,
+            build_root: self.build_root_dir()?,
+            site_url: self.settings.site_url.trim_end_matches('/').to_string(),
+            links: post_links(self.posts.iter().map(|x| x.as_ref()), &self.settings.site_url),
+            images: self.images.clone(),
+            assets: self.assets.clone(),
+        };
+        register_functions(&mut self.theme.renderer, context);
+        Ok(())
+    }
+
+    /// blog taxonomy settings.
+    ///
+    /// the `authors` taxonomy is added if blog has authors but does not declare it.
+    fn taxonomy_settings(&self) -> Vec<TaxonomySettings> {
+        let mut taxonomies = self.settings.taxonomies.clone();
+        if !self.settings.authors.is_empty() && !taxonomies.iter().any(|t| t.name == "authors") {
+            taxonomies.push(TaxonomySettings::authors());
+        }
+        taxonomies
+    }
+
+    /// get author of `id`, the author name is `id` if it is not declared in settings.
+    pub fn author(&self, id: &str) -> Author {
+        match self.settings.authors.get(id) {
+            Some(author) if author.name.is_empty() => Author {
+                id: id.to_string(),
+                name: id.to_string(),
+                ..author.clone()
+            },
+            Some(author) => Author {
+                id: id.to_string(),
+                ..author.clone()
+            },
+            None => Author::new(id),
+        }
+    }
+
     /// build index pages
     pub fn build_index_pages(&mut self) -> Result<()> {
-        let posts: Vec<_> = self.posts.iter().filter(|p| !p.headers.hidden).collect();
-        let total = posts.len();
-        let n = (total + self.settings.posts_per_page - 1) / self.settings.posts_per_page;
-        let mut i = 1;
-        while i <= n {
-            let start = (i - 1) * self.settings.posts_per_page;
-            let end = total.min(start + self.settings.posts_per_page);
-            let page = Page {
-                index: i,
-                name: format_page_name("index", i, total),
-                posts: posts[start..end].to_vec().into_iter().map(|p| p.to_owned()).collect(),
-            };
-            self.index_pages.push(Rc::new(page));
-            i += 1;
+        for language in &mut self.languages {
+            language.build_index_pages(self.settings.posts_per_page);
         }
         Ok(())
     }
 
-    /// build tag pages
-    pub fn build_tag_pages(&mut self) -> Result<()> {
-        for tag in self.tags_map.values() {
-            let total = tag.posts.len();
-            let n = (total + self.settings.posts_per_page - 1) / self.settings.posts_per_page;
-            let mut i = 1;
-            while i <= n {
-                let start = (i - 1) * self.settings.posts_per_page;
-                let end = total.min(start + self.settings.posts_per_page);
-                let page = Page {
-                    index: i,
-                    name: format_page_name(&tag.name, i, total),
-                    posts: tag.posts[start..end].to_vec().into_iter().collect(),
-                };
-                let pages = self.tag_pages.entry(tag.name.clone()).or_insert(Vec::new());
-                pages.push(Rc::new(page));
-                i += 1;
-            }
+    /// build taxonomy term pages
+    pub fn build_taxonomy_pages(&mut self) -> Result<()> {
+        for language in &mut self.languages {
+            language.build_taxonomy_pages(self.settings.posts_per_page);
         }
         Ok(())
     }
@@ -215,19 +354,77 @@ impl Mdblog {
     pub fn build(&mut self) -> Result<()> {
         self.load_posts()?;
         self.build_index_pages()?;
-        self.build_tag_pages()?;
+        self.build_taxonomy_pages()?;
         self.export_media()?;
+        self.export_images()?;
         self.export_static()?;
+        self.register_template_functions()?;
         self.export_posts()?;
-        self.export_index()?;
-        self.export_tags()?;
-        for tag in self.tags_map.values() {
-            self.export_tag(tag)?;
+        for language in &self.languages {
+            self.export_index(language)?;
+            for taxonomy in language.taxonomies.values() {
+                self.export_taxonomy(language, taxonomy)?;
+            }
+            self.export_atom(language)?;
         }
-        self.export_atom()?;
         Ok(())
     }
 
+    /// check all blog posts without writing output, return all the problems found.
+    ///
+    /// diagrams are not rendered when checking.
+    pub fn check(&self) -> Result<Vec<Problem>> {
+        let mut problems = Vec::new();
+        let mut posts: Vec<Post> = Vec::new();
+        for (post_path, post) in self.read_posts()? {
+            match post {
+                Ok((post, errors)) => {
+                    problems.extend(errors.iter().map(|e| Problem::from_error(&post_path, e)));
+                    posts.push(post);
+                }
+                Err(e) => problems.push(Problem::from_error(&post_path, &e)),
+            }
+        }
+
+        let site_url = self.settings.site_url.trim_end_matches('/');
+        let links = post_links(posts.iter(), site_url);
+        let diagrams = DiagramRenderer::default();
+        let media_root = self.media_root_dir()?;
+        for post in &mut posts {
+            match post.render(&self.settings, &self.theme, &diagrams, &links) {
+                Ok(unresolved) => {
+                    for link in unresolved {
+                        problems.push(Problem::error(&post.path, format!("unresolved internal link {}", link)));
+                    }
+                }
+                Err(e) => {
+                    problems.push(Problem::from_error(&post.path, &e));
+                    continue;
+                }
+            }
+            problems.extend(lint::check_post(post, site_url, &media_root));
+        }
+        problems.extend(lint::check_duplicate_urls(&posts));
+        for taxonomy in self.taxonomy_settings() {
+            problems.extend(lint::check_term_cases(&posts, &taxonomy.name));
+        }
+        problems.sort_by(|p1, p2| p1.path.cmp(&p2.path).then(p1.level.cmp(&p2.level)));
+        Ok(problems)
+    }
+
+    /// build the blog and check the links of generated html files.
+    ///
+    /// external links are checked against the url prefixes of `allowlist` file if it is given.
+    pub fn check_links(&mut self, allowlist: Option<&Path>) -> Result<Vec<BrokenLink>> {
+        let allowlist = match allowlist {
+            Some(path) => Some(LinkChecker::parse_allowlist(&std::fs::read_to_string(path)?)),
+            None => None,
+        };
+        self.build()?;
+        let mut checker = LinkChecker::new(self.build_root_dir()?, &self.settings.site_url, allowlist);
+        checker.check()
+    }
+
     /// serve the blog static files in the `build_dir` directory.
     pub fn serve(&mut self, host: String, port: u16) -> Result<()> {
         let addr_str = format!("{}:{}", host, port);
@@ -293,12 +490,30 @@ impl Mdblog {
     }
 
     /// rebuild blog
+    ///
+    /// the theme is reloaded, the last good theme is kept if the reloaded one fails to load or render.
     pub fn rebuild(&mut self) -> Result<()> {
         info!("Rebuild blog again...");
         let site_url = self.settings.site_url.clone();
-        self.load_customize_settings()?;
+        self.load_settings()?;
         self.settings.site_url = site_url;
-        self.build()?;
+        let last_theme = match Theme::new(self.theme_root_dir()?, &self.settings.theme) {
+            Ok(theme) => Some(std::mem::replace(&mut self.theme, theme)),
+            Err(ref e) => {
+                crate::utils::log_error_chain(e);
+                warn!("theme({}) reload failed, keep the last good theme", self.settings.theme);
+                None
+            }
+        };
+        match self.build() {
+            Err(ref e @ Error::ThemeTemplate(..)) if last_theme.is_some() => {
+                crate::utils::log_error_chain(e);
+                warn!("theme({}) render failed, keep the last good theme", self.settings.theme);
+                self.theme = last_theme.expect("last theme error");
+                self.build()?;
+            }
+            result => result?,
+        }
         info!("Rebuild done!");
         Ok(())
     }
@@ -322,6 +537,11 @@ impl Mdblog {
         get_dir(&self.root, &self.settings.media_dir)
     }
 
+    /// blog cache root directory absolute path.
+    pub fn cache_root_dir(&self) -> Result<PathBuf> {
+        get_dir(&self.root, &self.settings.cache_dir)
+    }
+
     /// blog posts root directory.
     pub fn post_root_dir(&self) -> Result<PathBuf> {
         Ok(self.root.join("posts"))
@@ -340,6 +560,12 @@ impl Mdblog {
             .expect("get build dir error")
             .to_string();
         patterns.push(Pattern::new(&format!("{}/**/*", build_dir.trim_end_matches('/')))?);
+        let cache_dir = self
+            .cache_root_dir()?
+            .to_str()
+            .expect("get cache dir error")
+            .to_string();
+        patterns.push(Pattern::new(&format!("{}/**/*", cache_dir.trim_end_matches('/')))?);
         Ok(patterns)
     }
 
@@ -407,10 +633,18 @@ impl Mdblog {
         Ok(())
     }
 
+    /// export the processed images of posts.
+    pub fn export_images(&self) -> Result<()> {
+        debug!("exporting processed images ...");
+        self.images.export(self.build_root_dir()?)
+    }
+
     /// export blog static files.
-    pub fn export_static(&self) -> Result<()> {
+    pub fn export_static(&mut self) -> Result<()> {
         let build_dir = self.build_root_dir()?;
-        self.theme.export_static(&build_dir)?;
+        let mut assets = AssetManifest::new(self.settings.fingerprint_assets, self.settings.asset_integrity);
+        self.theme.export_static(&build_dir, &mut assets)?;
+        self.assets = assets;
         Ok(())
     }
 
@@ -421,137 +655,277 @@ impl Mdblog {
             let dest = build_dir.join(post.dest());
             let html = self.render_post(post)?;
             write_file(&dest, html.as_bytes())?;
+            self.export_post_assets(post, &dest)?;
         }
         Ok(())
     }
 
-    /// export blog index page.
-    pub fn export_index(&self) -> Result<()> {
-        let build_dir = self.build_root_dir()?;
-        for (i, page) in self.index_pages.iter().enumerate() {
-            let dest = build_dir.join(&page.name);
-            let html = self.render_index(i)?;
-            write_file(&dest, html.as_bytes())?;
+    /// export bundle post assets next to the post html file `dest`.
+    fn export_post_assets(&self, post: &Post, dest: &Path) -> Result<()> {
+        let (src_dir, dest_dir) = match (post.src().parent(), dest.parent()) {
+            (Some(src_dir), Some(dest_dir)) => (src_dir.to_owned(), dest_dir.to_owned()),
+            _ => return Ok(()),
+        };
+        for asset in &post.assets {
+            let asset_dest = dest_dir.join(asset);
+            if let Some(p) = asset_dest.parent() {
+                std::fs::create_dir_all(p)?;
+            }
+            std::fs::copy(src_dir.join(asset), asset_dest)?;
         }
         Ok(())
     }
 
-    /// export blog tags page.
-    pub fn export_tags(&self) -> Result<()> {
-        let build_dir = self.build_root_dir()?;
-        let dest = build_dir.join("tags.html");
-        let html = self.render_tags()?;
-        write_file(&dest, html.as_bytes())?;
+    /// export blog index page of `language`.
+    pub fn export_index(&self, language: &Language) -> Result<()> {
+        let build_dir = self.build_root_dir()?.join(&language.prefix);
+        for (i, page) in language.index_pages.iter().enumerate() {
+            let dest = build_dir.join(&page.name);
+            let html = self.render_index(language, i)?;
+            write_file(&dest, html.as_bytes())?;
+        }
         Ok(())
     }
 
-    /// export blog tag index page.
-    pub fn export_tag(&self, tag: &Tag) -> Result<()> {
-        let build_dir = self.build_root_dir()?;
-        if let Some(pages) = self.tag_pages.get(&tag.name) {
-            for (i, page) in pages.iter().enumerate() {
-                let dest = build_dir.join("tags").join(&page.name);
-                debug!("rendering tag: {} ...", dest.display());
-                let html = self.render_tag(tag, i)?;
-                write_file(&dest, html.as_bytes())?;
+    /// export blog taxonomy list page, term pages and term feeds of `language`.
+    pub fn export_taxonomy(&self, language: &Language, taxonomy: &Taxonomy) -> Result<()> {
+        let build_dir = self.build_root_dir()?.join(&language.prefix);
+        let path = &taxonomy.settings.path;
+        let dest = build_dir.join(format!("{}.html", path));
+        let html = self.render_taxonomy(language, taxonomy)?;
+        write_file(&dest, html.as_bytes())?;
+        for tag in taxonomy.terms.values() {
+            if let Some(pages) = taxonomy.pages.get(&tag.name) {
+                for (i, page) in pages.iter().enumerate() {
+                    let dest = build_dir.join(path).join(&page.name);
+                    debug!("rendering {}: {} ...", taxonomy.name(), dest.display());
+                    let html = self.render_term(language, taxonomy, tag, i)?;
+                    write_file(&dest, html.as_bytes())?;
+                }
+            }
+            if taxonomy.settings.feed {
+                let feed_path = format!("{}/{}.xml", path, tag.name);
+                let xml = self.render_atom(language, &tag.posts, &feed_path)?;
+                write_file(&build_dir.join(&feed_path), xml.as_bytes())?;
             }
         }
         Ok(())
     }
 
-    /// export blog atom.xml
-    pub fn export_atom(&self) -> Result<()> {
+    /// export blog atom.xml of `language`.
+    pub fn export_atom(&self, language: &Language) -> Result<()> {
         debug!("rendering atom ...");
-        let build_dir: PathBuf = self.build_root_dir()?;
+        let build_dir: PathBuf = self.build_root_dir()?.join(&language.prefix);
         let dest: PathBuf = build_dir.join("atom.xml");
-        let now: OffsetDateTime = OffsetDateTime::now_local()?;
-        let mut context: Context = self.get_base_context()?;
-        context.insert("now", &now.format(&Rfc3339)?);
-        context.insert("posts", &self.posts[..10.min(self.posts.len())]);
-        let html = self.theme.renderer.render("atom.tpl", &context)?;
-        write_file(&dest, html.as_bytes())?;
+        let xml = self.render_atom(language, &language.posts, "atom.xml")?;
+        write_file(&dest, xml.as_bytes())?;
         Ok(())
     }
 
+    /// get the language of `code`, or the default language.
+    fn language(&self, code: &str) -> &Language {
+        self.languages
+            .iter()
+            .find(|x| x.code == code)
+            .unwrap_or(&self.languages[0])
+    }
+
+    /// statistics of visible posts of all languages.
+    fn site_stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        for language in &self.languages {
+            stats.add(&language.posts);
+        }
+        stats
+    }
+
     /// get base context of `theme.renderer` templates
-    fn get_base_context(&self) -> Result<Context> {
+    fn get_base_context(&self, language: &Language) -> Result<Context> {
         let mut context = Context::new();
-        context.insert("config", &self.settings);
-        let mut tags = self.tags_map.values().collect::<Vec<_>>();
-        tags.sort_by_key(|x| x.name.to_lowercase());
-        context.insert("tags", &tags);
-        context.insert("tag_map", &self.tags_map);
-        context.insert("index_pages", &self.index_pages);
-        context.insert("tag_pages", &self.tag_pages);
+        context.insert("config", &self.settings.localize(&language.code));
+        context.insert("lang", &language.code);
+        context.insert("lang_url", &language.url(&self.settings.site_url));
+        let languages: BTreeMap<_, _> = self
+            .languages
+            .iter()
+            .map(|x| (x.code.clone(), x.url(&self.settings.site_url)))
+            .collect();
+        context.insert("languages", &languages);
+        context.insert("taxonomies", &language.taxonomies);
+        context.insert("stats", &language.stats());
+        context.insert("site_stats", &self.site_stats());
+        match language.taxonomies.get("tags") {
+            Some(tags) => {
+                context.insert("tags", &tags.sorted_terms());
+                context.insert("tag_map", &tags.terms);
+                context.insert("tag_pages", &tags.pages);
+            }
+            None => {
+                context.insert("tags", &Vec::<Tag>::new());
+                context.insert("tag_map", &BTreeMap::<String, Tag>::new());
+                context.insert("tag_pages", &BTreeMap::<String, Vec<Rc<Page>>>::new());
+            }
+        }
+        context.insert("index_pages", &language.index_pages);
+        context.insert("theme_options", &self.theme.options(&self.settings.theme_options));
         Ok(context)
     }
 
     /// render post.html.
     pub fn render_post(&self, post: &Post) -> Result<String> {
         debug!("rendering post({}) ...", post.path.display());
-        let mut context = self.get_base_context()?;
+        let mut context = self.get_base_context(self.language(&post.lang))?;
         context.insert("post", &post);
-        Ok(self.theme.renderer.render("post.tpl", &context)?)
+        let template = match post.headers.template.as_str() {
+            "" => "post.tpl",
+            template => template,
+        };
+        self.theme.render(template, &context)
     }
 
     /// render index*.html.
-    pub fn render_index(&self, i: usize) -> Result<String> {
+    pub fn render_index(&self, language: &Language, i: usize) -> Result<String> {
         debug!("rendering index ...");
-        let mut context = self.get_base_context()?;
-        context.insert("page", &self.index_pages[i]);
-        context.insert("posts", &self.index_pages[i].posts);
-        Ok(self.theme.renderer.render("index.tpl", &context)?)
-    }
-
-    /// render tags.html.
-    pub fn render_tags(&self) -> Result<String> {
-        debug!("rendering tags ...");
-        let context = self.get_base_context()?;
-        Ok(self.theme.renderer.render("tags.tpl", &context)?)
+        let mut context = self.get_base_context(language)?;
+        context.insert("page", &language.index_pages[i]);
+        context.insert("posts", &language.index_pages[i].posts);
+        self.theme.render("index.tpl", &context)
+    }
+
+    /// render taxonomy list page, such as tags.html.
+    pub fn render_taxonomy(&self, language: &Language, taxonomy: &Taxonomy) -> Result<String> {
+        debug!("rendering {} ...", taxonomy.name());
+        let mut context = self.get_base_context(language)?;
+        context.insert("taxonomy", &taxonomy);
+        context.insert("terms", &taxonomy.sorted_terms());
+        if taxonomy.name() == "authors" {
+            let authors: Vec<_> = taxonomy.sorted_terms().iter().map(|t| self.author(&t.name)).collect();
+            context.insert("authors", &authors);
+        }
+        self.theme.render(&taxonomy.settings.list_template, &context)
     }
 
-    /// render tag.html.
-    pub fn render_tag(&self, tag: &Tag, i: usize) -> Result<String> {
-        debug!("rendering tag ...");
-        let mut context = self.get_base_context()?;
-        let page = self.tag_pages.get(&tag.name).unwrap().get(i).unwrap();
+    /// render taxonomy term page, such as tags/*.html.
+    pub fn render_term(&self, language: &Language, taxonomy: &Taxonomy, tag: &Tag, i: usize) -> Result<String> {
+        debug!("rendering {} term ...", taxonomy.name());
+        let mut context = self.get_base_context(language)?;
+        let pages = taxonomy.pages.get(&tag.name).unwrap();
+        let page = pages.get(i).unwrap();
+        context.insert("taxonomy", &taxonomy);
         context.insert("tag", &tag);
+        if taxonomy.name() == "authors" {
+            context.insert("author", &self.author(&tag.name));
+        }
+        context.insert("pages", &pages);
         context.insert("page", &page);
         context.insert("posts", &page.posts);
-        Ok(self.theme.renderer.render("tag.tpl", &context)?)
+        self.theme.render(&taxonomy.settings.term_template, &context)
+    }
+
+    /// render atom feed of `posts`, `feed_path` is the feed path relative to `language` url.
+    pub fn render_atom(&self, language: &Language, posts: &[Rc<Post>], feed_path: &str) -> Result<String> {
+        let now: OffsetDateTime = OffsetDateTime::now_local()?;
+        let mut context: Context = self.get_base_context(language)?;
+        let feed_path = Path::new(&language.prefix).join(feed_path);
+        context.insert("now", &now.format(&Rfc3339)?);
+        context.insert("feed_path", &feed_path);
+        context.insert("posts", &posts[..10.min(posts.len())]);
+        self.theme.render("atom.tpl", &context)
     }
 
-    /// list blog themes.
+    /// list blog themes with their manifests, and the embedded themes not overridden by theme directories.
     pub fn list_blog_theme(&self) -> Result<()> {
         let theme_root = self.theme_root_dir()?;
-        if !theme_root.exists() || !theme_root.is_dir() {
-            error!("no theme");
-        }
-        for entry in std::fs::read_dir(theme_root)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                println!(
-                    "* {}",
-                    path.file_name()
-                        .expect("theme name error")
-                        .to_str()
-                        .expect("theme name error")
-                );
+        let mut paths = Vec::new();
+        if theme_root.is_dir() {
+            for entry in std::fs::read_dir(theme_root)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        let mut names = Vec::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .expect("theme name error")
+                .to_str()
+                .expect("theme name error");
+            match ThemeManifest::load(&path, name) {
+                Ok(manifest) => println!("{}", theme_line(name, &manifest, false)),
+                Err(e) => println!("* {}: {}", name, e),
+            }
+            names.push(name.to_string());
+        }
+        for manifest in ThemeManifest::builtins() {
+            if !names.contains(&manifest.name) {
+                println!("{}", theme_line(&manifest.name, &manifest, true));
             }
         }
         Ok(())
     }
 
-    /// create a new blog theme as same as the current blog theme.
-    pub fn create_blog_theme(&self, name: &str) -> Result<()> {
-        self.theme.init_dir(name)?;
+    /// create a new blog theme as same as the `from` theme, or the current blog theme.
+    pub fn create_blog_theme(&self, name: &str, from: Option<&str>) -> Result<()> {
+        match from {
+            Some(from) => Theme::new(self.theme_root_dir()?, from)?.init_dir(name)?,
+            None => self.theme.init_dir(name)?,
+        }
+        Ok(())
+    }
+
+    /// install a blog theme from a `.tar.gz`/`.zip` archive or a git repository.
+    ///
+    /// the theme name is `name`, or the manifest name, or the source name.
+    /// the existing theme is overwritten only if `force` is true, and kept if the installed theme fails to load.
+    pub fn install_blog_theme(&self, source: &str, name: Option<&str>, force: bool) -> Result<()> {
+        let theme_root = self.theme_root_dir()?;
+        std::fs::create_dir_all(&theme_root)?;
+        let staging = TempBuilder::new().prefix(".install.").tempdir_in(&theme_root)?;
+        let fetched = fetch_theme(source, &staging.path().join("theme"))?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => {
+                let manifest = ThemeManifest::load(&fetched, source)?;
+                if manifest.name.is_empty() {
+                    source_name(source)
+                } else {
+                    manifest.name
+                }
+            }
+        };
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(Error::ThemeInstall(
+                source.into(),
+                format!("invalid theme name {:?}", name),
+            ));
+        }
+
+        let dest = theme_root.join(&name);
+        let backup = staging.path().join("backup");
+        if dest.exists() {
+            if !force {
+                return Err(Error::ThemeExisted(name));
+            }
+            std::fs::rename(&dest, &backup)?;
+        }
+        std::fs::rename(&fetched, &dest)?;
+        if let Err(e) = Theme::new(&theme_root, &name) {
+            std::fs::remove_dir_all(&dest)?;
+            if backup.exists() {
+                std::fs::rename(&backup, &dest)?;
+            }
+            return Err(e);
+        }
+        info!("theme({}) installed", name);
         Ok(())
     }
 
     /// delete a blog theme.
     pub fn delete_blog_theme(&self, name: &str) -> Result<()> {
-        if self.settings.theme == name {
+        if self.settings.theme == name || self.theme.parents.iter().any(|x| x == name) {
             return Err(Error::ThemeInUse(name.into()));
         }
         let theme_path = self.theme_root_dir()?.join(name);
@@ -564,19 +938,32 @@ impl Mdblog {
 
     /// set blog theme.
     pub fn set_blog_theme(&mut self, name: &str) -> Result<()> {
-        let theme_path = self.theme_root_dir()?.join(name);
-        if !theme_path.exists() || !theme_path.is_dir() {
-            return Err(Error::ThemeNotFound(name.into()));
-        }
+        Theme::new(self.theme_root_dir()?, name)?;
         self.settings.theme = name.to_string();
         self.export_config()?;
         Ok(())
     }
 }
 
-/// check directory entry is a hidden file.
-fn is_hidden(entry: &DirEntry) -> bool {
-    entry.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+/// the `theme list` line of theme `name`.
+fn theme_line(name: &str, manifest: &ThemeManifest, builtin: bool) -> String {
+    let mut line = format!("* {}", name);
+    if !manifest.version.is_empty() {
+        line.push_str(&format!(" {}", manifest.version));
+    }
+    if builtin {
+        line.push_str(" (built-in)");
+    }
+    if let Some(ref parent) = manifest.parent {
+        line.push_str(&format!(" (parent: {})", parent));
+    }
+    if let Some(ref required) = manifest.mdblog_version {
+        line.push_str(&format!(" (mdblog {})", required));
+    }
+    if !manifest.description.is_empty() {
+        line.push_str(&format!(": {}", manifest.description));
+    }
+    line
 }
 
 /// check directory entry is an markdown file.
@@ -598,6 +985,17 @@ fn is_markdown_file(entry: &DirEntry) -> bool {
     }
 }
 
+/// the site urls of `posts` keyed by post path, used by `@/` internal links.
+fn post_links<'a>(posts: impl Iterator<Item = &'a Post>, site_url: &str) -> HashMap<PathBuf, String> {
+    let site_url = site_url.trim_end_matches('/');
+    posts
+        .map(|post| {
+            let url = percent_encode(&post.url.to_string_lossy());
+            (post.path.clone(), format!("{}{}", site_url, url))
+        })
+        .collect()
+}
+
 /// create a directory pathbuf from setting config.
 fn get_dir<P: AsRef<Path>>(root: P, value: &str) -> Result<PathBuf> {
     let expanded_path = shellexpand::full(value)?.into_owned();
@@ -608,16 +1006,3 @@ fn get_dir<P: AsRef<Path>>(root: P, value: &str) -> Result<PathBuf> {
         return Ok(dir);
     }
 }
-
-fn format_page_name(prefix: &str, page: usize, total: usize) -> String {
-    if page == 0 || page > total {
-        return String::default();
-    }
-    let mut s = String::with_capacity(prefix.len() + 10);
-    s.push_str(prefix);
-    if page > 1 {
-        s.push_str(&format!("-{}", page));
-    }
-    s.push_str(".html");
-    s
-}
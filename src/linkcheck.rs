@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::utils::percent_decode;
+
+/// broken link found in the generated html file
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    /// html file path relative to the build directory
+    pub page: PathBuf,
+    /// the link `href` or `src`
+    pub link: String,
+    /// the broken reason
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.page.display(), self.link, self.reason)
+    }
+}
+
+/// link checker of the generated html files
+///
+/// internal links must be resolved to a file of the build directory, and to a heading id if with anchor.
+/// external links are checked against the url prefixes of `allowlist` if it is given.
+pub struct LinkChecker {
+    /// build root directory
+    root: PathBuf,
+    /// blog site url
+    site_url: String,
+    /// the path part of site url, such as `/blog`
+    base_path: String,
+    /// allowed url prefixes of external links
+    allowlist: Option<Vec<String>>,
+    /// cached ids of html files
+    ids: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl LinkChecker {
+    /// create new `LinkChecker`
+    pub fn new<P: AsRef<Path>>(root: P, site_url: &str, allowlist: Option<Vec<String>>) -> LinkChecker {
+        let site_url = site_url.trim_end_matches('/').to_string();
+        let base_path = match site_url.find("://") {
+            Some(i) => site_url[i + 3..]
+                .find('/')
+                .map(|j| &site_url[i + 3 + j..])
+                .unwrap_or(""),
+            None => site_url.as_str(),
+        }
+        .to_string();
+        LinkChecker {
+            root: root.as_ref().to_owned(),
+            site_url,
+            base_path,
+            allowlist,
+            ids: HashMap::new(),
+        }
+    }
+
+    /// parse the allowlist file, one url prefix per line, `#` starts a comment line.
+    pub fn parse_allowlist(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    /// check the links of all html files in the build directory.
+    pub fn check(&mut self) -> Result<Vec<BrokenLink>> {
+        let mut pages = Vec::new();
+        for entry in WalkDir::new(&self.root).sort_by_file_name() {
+            let entry = entry.expect("get walker entry error");
+            if entry.file_type().is_file() && entry.path().extension().map(|x| x == "html").unwrap_or(false) {
+                pages.push(entry.path().strip_prefix(&self.root)?.to_owned());
+            }
+        }
+        let mut broken = Vec::new();
+        for page in pages {
+            let html = std::fs::read_to_string(self.root.join(&page))?;
+            for link in attr_values(&html, &["href", "src"]) {
+                if let Err(reason) = self.check_link(&page, &link) {
+                    broken.push(BrokenLink {
+                        page: page.clone(),
+                        link,
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(broken)
+    }
+
+    /// check the `link` of html `page`, return the broken reason.
+    fn check_link(&mut self, page: &Path, link: &str) -> std::result::Result<(), String> {
+        let link = link.trim();
+        if link.is_empty() {
+            return Err("empty link".to_string());
+        }
+        let lower = link.to_lowercase();
+        if ["mailto:", "tel:", "javascript:", "data:"]
+            .iter()
+            .any(|x| lower.starts_with(x))
+        {
+            return Ok(());
+        }
+
+        let has_scheme = link
+            .find(':')
+            .map(|i| !link[..i].contains(['/', '#', '?']))
+            .unwrap_or(false);
+        let internal = if !self.site_url.is_empty() && link.starts_with(&self.site_url) {
+            Some(format!("{}{}", self.base_path, &link[self.site_url.len()..]))
+        } else if link.starts_with("//") || has_scheme {
+            None
+        } else {
+            Some(link.to_string())
+        };
+        let internal = match internal {
+            Some(internal) => internal,
+            None => return self.check_external(link),
+        };
+
+        let (path, anchor) = match internal.find('#') {
+            Some(i) => (&internal[..i], Some(&internal[i + 1..])),
+            None => (internal.as_str(), None),
+        };
+        let path = path.split('?').next().unwrap_or_default();
+        let target = if path.is_empty() {
+            page.to_owned()
+        } else if let Some(path) = path.strip_prefix('/') {
+            let base = self.base_path.trim_start_matches('/');
+            let rel = if base.is_empty() {
+                Some(path)
+            } else if path == base {
+                Some("")
+            } else {
+                path.strip_prefix(base).and_then(|x| x.strip_prefix('/'))
+            };
+            match rel {
+                Some(rel) => PathBuf::from(percent_decode(rel)),
+                None => return Err(format!("outside of site url {}", self.site_url)),
+            }
+        } else {
+            page.parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(percent_decode(path))
+        };
+        let target = normalize(&target).ok_or_else(|| "outside of build directory".to_string())?;
+        let target = if target.as_os_str().is_empty() || self.root.join(&target).is_dir() {
+            target.join("index.html")
+        } else {
+            target
+        };
+        if !self.root.join(&target).is_file() {
+            return Err("file not found".to_string());
+        }
+
+        match anchor {
+            Some(anchor) if !anchor.is_empty() && target.extension().map(|x| x == "html").unwrap_or(false) => {
+                let anchor = percent_decode(anchor);
+                if self.page_ids(&target).contains(&anchor) {
+                    Ok(())
+                } else {
+                    Err(format!("anchor #{} not found", anchor))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// check the external `link` with the allowlist.
+    fn check_external(&self, link: &str) -> std::result::Result<(), String> {
+        match self.allowlist {
+            Some(ref allowlist) if !allowlist.iter().any(|prefix| link.starts_with(prefix.as_str())) => {
+                Err("external link not in allowlist".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// the element ids of html `page`.
+    fn page_ids(&mut self, page: &Path) -> &HashSet<String> {
+        let root = &self.root;
+        self.ids.entry(page.to_owned()).or_insert_with(|| {
+            let html = std::fs::read_to_string(root.join(page)).unwrap_or_default();
+            attr_values(&html, &["id", "name"]).into_iter().collect()
+        })
+    }
+}
+
+/// the values of `attrs` attributes in `html`, html entities are decoded.
+pub(crate) fn attr_values(html: &str, attrs: &[&str]) -> Vec<String> {
+    let mut values = Vec::new();
+    let bytes = html.as_bytes();
+    let mut pos = 0;
+    while let Some(found) = html[pos..].find('=') {
+        let eq = pos + found;
+        pos = eq + 1;
+        let name_end = html[..eq].trim_end_matches([' ', '\t', '\n']).len();
+        let name_start = html[..name_end]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        if name_start == 0 || !bytes[name_start - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let name = &html[name_start..name_end];
+        if !attrs.iter().any(|x| x.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        let rest = html[eq + 1..].trim_start_matches([' ', '\t', '\n']);
+        let quote = match rest.chars().next() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => continue,
+        };
+        if let Some(end) = rest[1..].find(quote) {
+            values.push(decode_entities(&rest[1..1 + end]));
+        }
+    }
+    values
+}
+
+/// decode the basic html entities.
+fn decode_entities(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// normalize the relative `path`, return `None` if it is outside of the root.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(x) => normalized.push(x),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(normalized)
+}
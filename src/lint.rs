@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::linkcheck::attr_values;
+use crate::post::Post;
+
+/// level of content problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// the blog can not be built, or is built with broken content
+    Error,
+    /// the blog can be built, but the content should be improved
+    Warning,
+}
+
+/// content problem found by `mdblog check`
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    /// problem level
+    pub level: Level,
+    /// post path relative to the blog root directory
+    pub path: PathBuf,
+    /// problem message
+    pub message: String,
+}
+
+impl Problem {
+    /// create new error `Problem`
+    pub fn error<P: Into<PathBuf>, S: Into<String>>(path: P, message: S) -> Problem {
+        Problem {
+            level: Level::Error,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// create new warning `Problem`
+    pub fn warning<P: Into<PathBuf>, S: Into<String>>(path: P, message: S) -> Problem {
+        Problem {
+            level: Level::Warning,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// create new error `Problem` with the cause chain of `e`
+    pub fn from_error<P: Into<PathBuf>>(path: P, e: &dyn StdError) -> Problem {
+        let mut message = e.to_string();
+        let mut e = e;
+        while let Some(source) = e.source() {
+            message.push_str(": ");
+            message.push_str(&source.to_string());
+            e = source;
+        }
+        Problem::error(path, message)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", level, self.path.display(), self.message)
+    }
+}
+
+/// check the rendered `post`, the media links are resolved in `media_root`.
+pub fn check_post(post: &Post, site_url: &str, media_root: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if post.headers.title.is_empty() {
+        problems.push(Problem::warning(
+            &post.path,
+            "missing `title` header, the file name is used",
+        ));
+    }
+    if post.headers.description.is_empty() {
+        problems.push(Problem::warning(&post.path, "empty description"));
+    }
+    for img in img_tags(&post.content) {
+        if attr_values(img, &["alt"]).iter().all(|alt| alt.trim().is_empty()) {
+            let src = attr_values(img, &["src"]).pop().unwrap_or_default();
+            problems.push(Problem::warning(&post.path, format!("image {} without alt text", src)));
+        }
+    }
+
+    for link in attr_values(&post.content, &["href", "src"]) {
+        match post.local_path(&link, site_url, media_root) {
+            Some(target) if !target.exists() => {
+                problems.push(Problem::error(&post.path, format!("missing media file {}", link)));
+            }
+            _ => {}
+        }
+    }
+    problems
+}
+
+/// check the posts with the same url.
+pub fn check_duplicate_urls(posts: &[Post]) -> Vec<Problem> {
+    let mut urls: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+    for post in posts {
+        urls.entry(&post.url).or_default().push(&post.path);
+    }
+    let mut problems = Vec::new();
+    for (url, paths) in urls {
+        if paths.len() < 2 {
+            continue;
+        }
+        for path in &paths {
+            let others: Vec<String> = paths
+                .iter()
+                .filter(|x| x != &path)
+                .map(|x| x.display().to_string())
+                .collect();
+            let message = format!("duplicate url {} with {}", url.display(), others.join(", "));
+            problems.push(Problem::error(*path, message));
+        }
+    }
+    problems
+}
+
+/// check the terms of `taxonomy` differing only by case, across posts or within one post.
+pub fn check_term_cases(posts: &[Post], taxonomy: &str) -> Vec<Problem> {
+    let mut terms: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for post in posts {
+        for term in post.terms(taxonomy) {
+            terms.entry(term.to_lowercase()).or_default().insert(term);
+        }
+    }
+    let mut problems = Vec::new();
+    for spellings in terms.values().filter(|x| x.len() > 1) {
+        for post in posts {
+            let used: BTreeSet<&str> = post
+                .terms(taxonomy)
+                .into_iter()
+                .filter(|x| spellings.contains(x))
+                .collect();
+            let used: Vec<&str> = used.into_iter().collect();
+            if used.is_empty() {
+                continue;
+            }
+            let others: Vec<&str> = spellings.iter().filter(|x| !used.contains(x)).copied().collect();
+            let message = if others.is_empty() {
+                format!("{} {} differ only by case", taxonomy, used.join(", "))
+            } else {
+                format!(
+                    "{} {} differ only by case from {}",
+                    taxonomy,
+                    used.join(", "),
+                    others.join(", ")
+                )
+            };
+            problems.push(Problem::warning(&post.path, message));
+        }
+    }
+    problems
+}
+
+/// the `<img ...>` tags of `html`.
+fn img_tags(html: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = html[pos..].find("<img ") {
+        let start = pos + found;
+        let end = html[start..].find('>').map(|i| start + i + 1).unwrap_or(html.len());
+        tags.push(&html[start..end]);
+        pos = end;
+    }
+    tags
+}
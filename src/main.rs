@@ -3,7 +3,7 @@ use std::error::Error;
 use std::path::{Path, PathBuf};
 
 use clap::{Parser, Subcommand};
-use mdblog::{Mdblog, Result};
+use mdblog::{Level, Mdblog, Result};
 use tracing::error;
 
 /// static site generator from markdown files
@@ -40,6 +40,18 @@ enum CliCommand {
         /// Serve the blog at <port>
         port: u16,
     },
+    /// Check the blog posts without writing output
+    Check {
+        #[clap(long)]
+        /// Print the problems in json format
+        json: bool,
+    },
+    /// Build the blog and check the links of generated html files
+    CheckLinks {
+        #[clap(long)]
+        /// Allowed url prefixes of external links, one per line
+        allowlist: Option<PathBuf>,
+    },
     /// Blog theme operations
     Theme {
         #[clap(subcommand)]
@@ -55,6 +67,20 @@ enum ThemeCommand {
     New {
         /// theme name
         name: String,
+        #[clap(long)]
+        /// Copy from the theme, such as the built-in `docs` and `dark`, default is the current theme
+        from: Option<String>,
+    },
+    /// Install a theme from a .tar.gz/.zip archive or a git repository
+    Install {
+        /// archive path or git repository url
+        source: String,
+        #[clap(long)]
+        /// theme name, default is the manifest name or the source name
+        name: Option<String>,
+        #[clap(long)]
+        /// Overwrite the existing theme
+        force: bool,
     },
     /// Delete a theme
     Delete {
@@ -77,6 +103,8 @@ fn main() {
         CliCommand::New { ref tags, ref path } => new(path, tags),
         CliCommand::Build => build(),
         CliCommand::Serve { host, port } => serve(host, port),
+        CliCommand::Check { json } => check(json),
+        CliCommand::CheckLinks { ref allowlist } => check_links(allowlist.as_deref()),
         CliCommand::Theme { ref cmd } => theme(cmd),
     };
 
@@ -117,6 +145,43 @@ fn serve(host: String, port: u16) -> Result<()> {
     Ok(())
 }
 
+fn check(json: bool) -> Result<()> {
+    let root_dir = env::current_dir()?;
+    let mut mb = Mdblog::new(&root_dir)?;
+    mb.load_customize_settings()?;
+    let problems = mb.check()?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&problems).expect("problems serialized error")
+        );
+    } else {
+        for problem in &problems {
+            println!("{}", problem);
+        }
+    }
+    let errors = problems.iter().filter(|x| x.level == Level::Error).count();
+    if errors > 0 {
+        return Err(mdblog::Error::ContentErrors(errors));
+    }
+    Ok(())
+}
+
+fn check_links(allowlist: Option<&Path>) -> Result<()> {
+    let root_dir = env::current_dir()?;
+    let mut mb = Mdblog::new(&root_dir)?;
+    mb.load_customize_settings()?;
+    let broken = mb.check_links(allowlist)?;
+    for link in &broken {
+        println!("{}", link);
+    }
+    if !broken.is_empty() {
+        return Err(mdblog::Error::LinksBroken(broken.len()));
+    }
+    println!("no broken links");
+    Ok(())
+}
+
 fn theme(cmd: &ThemeCommand) -> Result<()> {
     let root_dir = env::current_dir()?;
     let mut mb = Mdblog::new(&root_dir)?;
@@ -124,7 +189,12 @@ fn theme(cmd: &ThemeCommand) -> Result<()> {
 
     match *cmd {
         ThemeCommand::List => mb.list_blog_theme()?,
-        ThemeCommand::New { ref name } => mb.create_blog_theme(name)?,
+        ThemeCommand::New { ref name, ref from } => mb.create_blog_theme(name, from.as_deref())?,
+        ThemeCommand::Install {
+            ref source,
+            ref name,
+            force,
+        } => mb.install_blog_theme(source, name.as_deref(), force)?,
         ThemeCommand::Delete { ref name } => mb.delete_blog_theme(name)?,
         ThemeCommand::Set { ref name } => mb.set_blog_theme(name)?,
     }
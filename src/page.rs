@@ -15,3 +15,38 @@ pub struct Page {
     /// page posts array
     pub posts: Vec<Rc<Post>>,
 }
+
+impl Page {
+    /// split `posts` into pages, `per_page` posts every page.
+    pub fn paginate(prefix: &str, posts: &[Rc<Post>], per_page: usize) -> Vec<Rc<Page>> {
+        let total = posts.len();
+        let n = total.div_ceil(per_page);
+        let mut pages = Vec::with_capacity(n);
+        let mut i = 1;
+        while i <= n {
+            let start = (i - 1) * per_page;
+            let end = total.min(start + per_page);
+            let page = Page {
+                index: i,
+                name: format_page_name(prefix, i, total),
+                posts: posts[start..end].to_vec(),
+            };
+            pages.push(Rc::new(page));
+            i += 1;
+        }
+        pages
+    }
+}
+
+fn format_page_name(prefix: &str, page: usize, total: usize) -> String {
+    if page == 0 || page > total {
+        return String::default();
+    }
+    let mut s = String::with_capacity(prefix.len() + 10);
+    s.push_str(prefix);
+    if page > 1 {
+        s.push_str(&format!("-{}", page));
+    }
+    s.push_str(".html");
+    s
+}
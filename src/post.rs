@@ -1,13 +1,25 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use time::OffsetDateTime;
 use tracing::debug;
+use walkdir::{DirEntry, WalkDir};
 
+use crate::author::Author;
+use crate::diagram::DiagramRenderer;
 use crate::error::{Error, Result};
-use crate::utils::markdown_to_html;
+use crate::settings::Settings;
+use crate::shortcode::render_shortcodes;
+use crate::theme::Theme;
+use crate::toc::TocItem;
+use crate::utils::{
+    count_words, expand_containers, is_hidden, markdown_summary, markdown_to_html, markdown_to_text, percent_decode,
+    resolve_internal_links,
+};
 
 /// blog post headers
 ///
@@ -23,18 +35,64 @@ pub struct PostHeaders {
     /// post tags, `tags: [hello, world]`, default `[]`
     #[serde(default)]
     pub tags: Vec<String>,
+    /// post categories, `categories: [rust/async]`, default `[]`
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// post author id, `author: fu`, merged into `authors` when loading
+    #[serde(default)]
+    pub author: String,
+    /// post author ids, `authors: [fu, bar]`, default `[]`
+    #[serde(default)]
+    pub authors: Vec<String>,
     /// post description
     #[serde(default)]
     pub description: String,
     /// post title
     #[serde(default)]
     pub title: String,
+    /// post table of contents flag, `toc: false`, default `true`
+    #[serde(default = "default_toc")]
+    pub toc: bool,
+    /// post language, `lang: zh`, default is from `post.<lang>.md` filename or blog default language
+    #[serde(default)]
+    pub lang: String,
+    /// post theme template, `template: page.tpl`, default is `post.tpl`
+    #[serde(default)]
+    pub template: String,
+    /// all unrecognised headers, `cover_image: /media/cover.png`, available as `post.headers.extra`
+    #[serde(flatten, serialize_with = "serialize_extra")]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// serialize the flatten `extra` headers as `extra` field.
+fn serialize_extra<S: Serializer>(
+    extra: &BTreeMap<String, serde_yaml::Value>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("extra", extra)?;
+    map.end()
+}
+
+/// translation of blog post
+#[derive(Debug, Clone, Serialize)]
+pub struct Translation {
+    /// translation language
+    pub lang: String,
+    /// translation title
+    pub title: String,
+    /// translation url
+    pub url: PathBuf,
 }
 
 /// blog post
 ///
 /// every blog post is composed of `head` part and `body` part.
-/// the two part is separated by the first blank line.
+/// the head is yaml front matter between `---` lines, or toml front matter between `+++` lines,
+/// otherwise the two part is separated by the first blank line.
+///
+/// a post can also be a bundle directory, `posts/my-post/index.md`,
+/// the other files in the directory are the post assets, exported next to the post html file.
 #[derive(Serialize)]
 pub struct Post {
     /// blog root path
@@ -49,77 +107,270 @@ pub struct Post {
     pub headers: PostHeaders,
     /// post html body
     pub content: String,
+    /// post html summary, the body before `<!-- more -->` marker, or the first paragraph
+    pub summary: String,
+    /// post plain text summary
+    pub summary_text: String,
+    /// post table of contents, empty if `toc: false` header is set
+    pub toc: Vec<TocItem>,
+    /// post word count, every Han or kana character is counted as a word
+    pub word_count: usize,
+    /// post estimated reading time in minutes
+    pub reading_time: usize,
+    /// post authors, resolved from `authors` settings
+    pub authors: Vec<Author>,
+    /// post language
+    pub lang: String,
+    /// the other language versions of the post
+    pub translations: Vec<Translation>,
+    /// bundle post assets, relative to the bundle directory
+    pub assets: Vec<PathBuf>,
+    /// whether the post is a bundle directory
+    #[serde(skip)]
+    is_bundle: bool,
+    /// post path without extension and language suffix, shared by translations
+    #[serde(skip)]
+    base: PathBuf,
+    /// post markdown body
+    #[serde(skip)]
+    body: String,
+    /// the line number of body first line in post file
+    #[serde(skip)]
+    body_line: usize,
 }
 
 impl Post {
-    /// create new `Post`
-    pub fn new<P: AsRef<Path>>(root: P, path: P) -> Result<Post> {
+    /// create new `Post`, the markdown body is rendered by `render` method.
+    ///
+    /// `index.md` and `index.<lang>.md` are bundle posts, `<lang>` is in `languages` or `default_language`.
+    pub fn new<P: AsRef<Path>>(root: P, path: P, languages: &[&str], default_language: &str) -> Result<Post> {
         let root = root.as_ref();
         let path = path.as_ref();
         debug!("loading post: {}", path.display());
 
-        let (headers, content) = Self::split_file(root, path)?;
+        let (headers, body, body_line) = Self::split_file(root, path)?;
+        let base = path.with_extension("");
+        let is_bundle = is_bundle_path(path, languages, default_language);
         let title = if headers.title.is_empty() {
-            path.file_stem()
-                .and_then(|x| x.to_str())
-                .expect(&format!("post filename format error: {}", path.display()))
+            default_title(&base, is_bundle)
         } else {
-            headers.title.as_ref()
+            headers.title.clone()
         };
         let url = Path::new("/").join(path).with_extension("html");
+        let assets = if is_bundle {
+            Self::bundle_assets(root, path, languages, default_language)?
+        } else {
+            Vec::new()
+        };
 
         Ok(Post {
             root: root.to_owned(),
             path: path.to_owned(),
-            title: title.to_owned(),
+            title,
             url,
             headers,
-            content,
+            content: String::new(),
+            summary: String::new(),
+            summary_text: String::new(),
+            toc: Vec::new(),
+            word_count: 0,
+            reading_time: 0,
+            authors: Vec::new(),
+            lang: String::new(),
+            translations: Vec::new(),
+            assets,
+            is_bundle,
+            base,
+            body,
+            body_line,
         })
     }
 
-    /// split a post into `headers` and `content`
-    fn split_file(root: &Path, path: &Path) -> Result<(PostHeaders, String)> {
+    /// render the markdown body, the theme shortcodes and `:::` containers are expanded before markdown,
+    /// the internal links `@/posts/other.md` are resolved with post `links`.
+    ///
+    /// return the unresolved internal links.
+    pub fn render(
+        &mut self,
+        settings: &Settings,
+        theme: &Theme,
+        diagrams: &DiagramRenderer,
+        links: &HashMap<PathBuf, String>,
+    ) -> Result<Vec<String>> {
+        let body = render_shortcodes(&self.body, &theme.shortcodes).map_err(|(offset, e)| {
+            let line = self.body_line + self.body[..offset].matches('\n').count();
+            Error::PostShortcode(e, self.path.clone(), line)
+        })?;
+        let body = expand_containers(&body);
+        let (body, unresolved) = resolve_internal_links(&body, links);
+
+        let (summary, summary_text) = markdown_summary(&body);
+        if self.headers.description.is_empty() {
+            let desc = summary_text.split_whitespace().take(100).collect::<Vec<_>>().join(" ");
+            self.headers.description.push_str(&desc);
+            if !self.headers.description.is_empty() {
+                self.headers.description.push_str("...");
+            }
+        }
+        let (content, mut toc) = markdown_to_html(&body, settings, diagrams);
+        if !self.headers.toc {
+            toc.clear();
+        }
+        let (words, cjk_chars) = count_words(&markdown_to_text(&body));
+        let minutes = words as f64 / settings.words_per_minute.max(1) as f64
+            + cjk_chars as f64 / settings.cjk_chars_per_minute.max(1) as f64;
+
+        self.content = content;
+        self.summary = summary;
+        self.summary_text = summary_text;
+        self.toc = toc;
+        self.word_count = words + cjk_chars;
+        self.reading_time = (minutes.ceil() as usize).max(1);
+        Ok(unresolved)
+    }
+
+    /// collect the assets of bundle post, nested bundles are excluded.
+    fn bundle_assets(root: &Path, path: &Path, languages: &[&str], default_language: &str) -> Result<Vec<PathBuf>> {
+        let bundle_dir = root.join(path.parent().expect("bundle post parent error"));
+        let mut assets = Vec::new();
+        let walker = WalkDir::new(&bundle_dir).min_depth(1).into_iter();
+        for entry in walker.filter_entry(|e| !is_hidden(e) && !is_bundle_dir(e, languages, default_language)) {
+            let entry = entry.expect("get walker entry error");
+            let asset = entry.path();
+            if !asset.is_file() || asset.extension().map(|x| x == "md").unwrap_or(false) {
+                continue;
+            }
+            assets.push(asset.strip_prefix(&bundle_dir)?.to_owned());
+        }
+        Ok(assets)
+    }
+
+    /// whether the post is a bundle directory.
+    pub fn is_bundle(&self) -> bool {
+        self.is_bundle
+    }
+
+    /// split a post into `headers`, markdown `body` and the line number of body.
+    fn split_file(root: &Path, path: &Path) -> Result<(PostHeaders, String, usize)> {
         let fp = root.join(path);
         let mut fo = File::open(fp)?;
         let mut content = String::new();
         fo.read_to_string(&mut content)?;
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
 
-        let v: Vec<&str> = content.splitn(2, "\n\n").collect();
-        if v.len() != 2 {
-            return Err(Error::PostOnlyOnePart(path.into()));
-        }
-        let head = v[0].trim();
-        let body = v[1].trim();
+        let (head, body, is_toml) = if let Some(rest) = strip_delimiter_line(&content, "---") {
+            let (head, body) = split_front_matter(rest, "---").ok_or(Error::PostOnlyOnePart(path.into()))?;
+            (head, body, false)
+        } else if let Some(rest) = strip_delimiter_line(&content, "+++") {
+            let (head, body) = split_front_matter(rest, "+++").ok_or(Error::PostOnlyOnePart(path.into()))?;
+            (head, body, true)
+        } else {
+            let v: Vec<&str> = content.splitn(2, "\n\n").collect();
+            if v.len() != 2 {
+                return Err(Error::PostOnlyOnePart(path.into()));
+            }
+            (v[0], v[1], false)
+        };
+        let head = head.trim();
+        let body = body.trim();
         if head.is_empty() {
             return Err(Error::PostNoHead(path.into()));
         }
         if body.is_empty() {
             return Err(Error::PostNoBody(path.into()));
         }
-        let mut headers: PostHeaders = match serde_yaml::from_str(head) {
-            Ok(headers) => headers,
-            Err(e) => {
-                return Err(Error::PostHeadPaser(e, path.into()));
+        let mut headers: PostHeaders = if is_toml {
+            parse_toml_headers(head).map_err(|e| Error::PostHeadTomlPaser(Box::new(e), path.into()))?
+        } else {
+            serde_yaml::from_str(head).map_err(|e| Error::PostHeadPaser(e, path.into()))?
+        };
+        if !headers.author.is_empty() && !headers.authors.contains(&headers.author) {
+            headers.authors.insert(0, headers.author.clone());
+        }
+        let body_line = content[..body.as_ptr() as usize - content.as_ptr() as usize]
+            .matches('\n')
+            .count()
+            + 1;
+        Ok((headers, body.to_string(), body_line))
+    }
+
+    /// set the post language.
+    ///
+    /// the language is from `lang` header, or `post.<lang>.md` filename if `<lang>` is in `languages`,
+    /// or `default_language`. the post url is prefixed with `/<lang>` if it is not the default language.
+    pub fn localize(&mut self, languages: &[&str], default_language: &str) {
+        let stem = Path::new(self.path.file_stem().unwrap_or_default());
+        let suffix = stem
+            .extension()
+            .and_then(|x| x.to_str())
+            .filter(|x| *x == default_language || languages.contains(x));
+        if let Some(suffix) = suffix {
+            self.base = self.path.with_file_name(stem.file_stem().unwrap_or_default());
+            if self.headers.title.is_empty() {
+                self.title = default_title(&self.base, self.is_bundle());
+            }
+            if self.headers.lang.is_empty() {
+                self.headers.lang = suffix.to_string();
             }
+        }
+        if self.headers.lang.is_empty() {
+            self.headers.lang = default_language.to_string();
+        }
+        self.lang = self.headers.lang.clone();
+        let url = Path::new("/");
+        let url = if self.lang == default_language {
+            url.to_owned()
+        } else {
+            url.join(&self.lang)
         };
-        if headers.description.is_empty() {
-            let desc = body
-                .split("\n\n")
-                .take(1)
-                .next()
-                .unwrap_or("")
-                .split_whitespace()
-                .take(100)
-                .collect::<Vec<_>>()
-                .join(" ");
-            headers.description.push_str(&desc);
-            if !headers.description.is_empty() {
-                headers.description.push_str("...");
+        let mut url = url.join(&self.base).into_os_string();
+        url.push(".html");
+        self.url = url.into();
+    }
+
+    /// the post path without extension and language suffix, translations of a post share it.
+    pub fn translation_key(&self) -> &Path {
+        &self.base
+    }
+
+    /// the terms of `taxonomy` the post belongs to.
+    ///
+    /// the terms of custom taxonomy are from the extra header of the same name,
+    /// `series: [rust]` or `series: rust`.
+    pub fn terms(&self, taxonomy: &str) -> Vec<&str> {
+        let terms = match taxonomy {
+            "tags" => &self.headers.tags,
+            "categories" => &self.headers.categories,
+            "authors" => &self.headers.authors,
+            _ => {
+                return match self.headers.extra.get(taxonomy) {
+                    Some(serde_yaml::Value::String(term)) => vec![term.as_str()],
+                    Some(serde_yaml::Value::Sequence(terms)) => terms.iter().filter_map(|x| x.as_str()).collect(),
+                    _ => Vec::new(),
+                };
             }
+        };
+        terms.iter().map(|x| x.as_str()).collect()
+    }
+
+    /// the local file of `link` in post content, `/media/` links are in `media_root`,
+    /// relative links of bundle post are in the bundle directory.
+    pub fn local_path(&self, link: &str, site_url: &str, media_root: &Path) -> Option<PathBuf> {
+        let path = link.split(['#', '?']).next().unwrap_or_default();
+        let site_url = site_url.trim_end_matches('/');
+        let path = if site_url.is_empty() {
+            path
+        } else {
+            path.strip_prefix(site_url).unwrap_or(path)
+        };
+        if let Some(media) = path.strip_prefix("/media/") {
+            Some(media_root.join(percent_decode(media)))
+        } else if self.is_bundle() && !path.is_empty() && !path.starts_with('/') && !path.contains(':') {
+            let bundle_dir = self.src().parent()?.to_owned();
+            Some(bundle_dir.join(percent_decode(path)))
+        } else {
+            None
         }
-        let content = markdown_to_html(body);
-        Ok((headers, content))
     }
 
     /// the absolute path of blog post markdown file.
@@ -127,8 +378,183 @@ impl Post {
         self.root.join(&self.path)
     }
 
-    /// the absolute path of blog post html file.
+    /// the relative path of blog post html file.
     pub fn dest(&self) -> PathBuf {
-        self.path.with_extension("html")
+        self.url.strip_prefix("/").unwrap_or(&self.url).to_owned()
+    }
+}
+
+/// strip the first line of `content` if it is the front matter `delimiter`.
+fn strip_delimiter_line<'a>(content: &'a str, delimiter: &str) -> Option<&'a str> {
+    let (line, rest) = content.split_once('\n').unwrap_or((content, ""));
+    if line.trim_end() == delimiter {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// split `content` into front matter and body at the closing `delimiter` line.
+fn split_front_matter<'a>(content: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((&content[..offset], &content[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// parse toml front matter, toml datetime values are converted to rfc3339 strings.
+fn parse_toml_headers(head: &str) -> std::result::Result<PostHeaders, toml::de::Error> {
+    fn datetime_to_string(value: toml::Value) -> toml::Value {
+        match value {
+            toml::Value::Datetime(dt) => toml::Value::String(dt.to_string()),
+            toml::Value::Array(values) => toml::Value::Array(values.into_iter().map(datetime_to_string).collect()),
+            toml::Value::Table(table) => {
+                toml::Value::Table(table.into_iter().map(|(k, v)| (k, datetime_to_string(v))).collect())
+            }
+            value => value,
+        }
+    }
+    let value: toml::Value = toml::from_str(head)?;
+    datetime_to_string(value).try_into()
+}
+
+fn default_toc() -> bool {
+    true
+}
+
+/// whether `path` is a bundle post, `posts/my-post/index.md` or `posts/my-post/index.<lang>.md`.
+fn is_bundle_path(path: &Path, languages: &[&str], default_language: &str) -> bool {
+    let name = path.file_name().and_then(|x| x.to_str()).unwrap_or_default();
+    is_bundle_index(name, languages, default_language) && path.components().count() > 2
+}
+
+/// whether directory entry is a bundle directory containing `index.md` or `index.<lang>.md`.
+fn is_bundle_dir(entry: &DirEntry, languages: &[&str], default_language: &str) -> bool {
+    if !entry.file_type().is_dir() {
+        return false;
+    }
+    match std::fs::read_dir(entry.path()) {
+        Ok(entries) => entries.filter_map(|x| x.ok()).any(|x| {
+            let name = x.file_name();
+            let name = name.to_str().unwrap_or_default();
+            is_bundle_index(name, languages, default_language) && x.path().is_file()
+        }),
+        Err(_) => false,
+    }
+}
+
+/// whether `name` is the bundle index file name, `index.md` or `index.<lang>.md`.
+fn is_bundle_index(name: &str, languages: &[&str], default_language: &str) -> bool {
+    if name == "index.md" {
+        return true;
+    }
+    match name.strip_prefix("index.").and_then(|x| x.strip_suffix(".md")) {
+        Some(lang) => lang == default_language || languages.contains(&lang),
+        None => false,
+    }
+}
+
+/// the post title from filename, or the directory name of bundle post.
+fn default_title(base: &Path, is_bundle: bool) -> String {
+    let name = if is_bundle {
+        base.parent().and_then(|x| x.file_name())
+    } else {
+        base.file_name()
+    };
+    name.and_then(|x| x.to_str())
+        .expect(&format!("post filename format error: {}", base.display()))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// render the post `content` with a shortcode `fail`, return the unresolved internal links.
+    fn render(content: &str, links: &HashMap<PathBuf, String>) -> Result<Vec<String>> {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("posts")).unwrap();
+        std::fs::write(root.path().join("posts/post.md"), content).unwrap();
+        let settings = Settings::default();
+        let mut theme = Theme::new(root.path().join("_themes"), "simple").unwrap();
+        theme.shortcodes.add_raw_template("fail", "{{ missing }}").unwrap();
+        let diagrams = DiagramRenderer::new(&settings.diagrams, root.path().join("_cache"));
+        let mut post = Post::new(root.path(), Path::new("posts/post.md"), &[], "en")?;
+        post.render(&settings, &theme, &diagrams, links)
+    }
+
+    fn split(content: &str) -> Result<(PostHeaders, String, usize)> {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("post.md"), content).unwrap();
+        Post::split_file(root.path(), Path::new("post.md"))
+    }
+
+    #[test]
+    fn split_blank_line_headers() {
+        let (headers, body, line) = split("created: 2024-01-01T00:00:00Z\ntags: [a]\n\nhello\n").unwrap();
+        assert_eq!(headers.tags, ["a"]);
+        assert_eq!(body, "hello");
+        assert_eq!(line, 4);
+    }
+
+    #[test]
+    fn split_yaml_front_matter_with_blank_lines() {
+        let content = "---\ncreated: 2024-01-01T00:00:00Z\n\ntitle: hi\n---\n\nhello\n";
+        let (headers, body, line) = split(content).unwrap();
+        assert_eq!(headers.title, "hi");
+        assert_eq!(body, "hello");
+        assert_eq!(line, 7);
+    }
+
+    #[test]
+    fn split_toml_front_matter() {
+        let content = "+++\ncreated = 2024-01-01T00:00:00Z\ntags = [\"a\", \"b\"]\n+++\nhello\n";
+        let (headers, body, line) = split(content).unwrap();
+        assert_eq!(headers.tags, ["a", "b"]);
+        assert_eq!(body, "hello");
+        assert_eq!(line, 5);
+    }
+
+    #[test]
+    fn split_crlf_front_matter() {
+        let content = "---\r\ncreated: 2024-01-01T00:00:00Z\r\ntitle: hi\r\n---\r\nhello\r\nworld\r\n";
+        let (headers, body, line) = split(content).unwrap();
+        assert_eq!(headers.title, "hi");
+        assert_eq!(body, "hello\nworld");
+        assert_eq!(line, 5);
+
+        let (headers, body, _) = split("created: 2024-01-01T00:00:00Z\r\ntitle: hi\r\n\r\nhello\r\n").unwrap();
+        assert_eq!(headers.title, "hi");
+        assert_eq!(body, "hello");
+    }
+
+    #[test]
+    fn split_unclosed_front_matter() {
+        let content = "---\ncreated: 2024-01-01T00:00:00Z\n\nhello\n";
+        assert!(matches!(split(content), Err(Error::PostOnlyOnePart(_))));
+    }
+
+    #[test]
+    fn shortcode_error_line() {
+        let content = "---\ncreated: 2024-01-01T00:00:00Z\n---\n\nline 5\n\n`{{ fail() }}`\n\n{{ fail() }}\n";
+        match render(content, &HashMap::new()) {
+            Err(Error::PostShortcode(_, path, line)) => {
+                assert_eq!(path, Path::new("posts/post.md"));
+                assert_eq!(line, 9);
+            }
+            _ => panic!("shortcode error expected"),
+        }
+    }
+
+    #[test]
+    fn report_unresolved_links() {
+        let content = "created: 2024-01-01T00:00:00Z\n\n[a](@/posts/other.md) [b](@/posts/missing.md)\n";
+        let mut links = HashMap::new();
+        links.insert(PathBuf::from("posts/other.md"), "/posts/other.html".to_string());
+        assert_eq!(render(content, &links).unwrap(), ["@/posts/missing.md"]);
     }
 }
@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use config::{ConfigError, Source, Value};
 use serde::{Deserialize, Serialize};
 
+use crate::author::Author;
+
 /// blog setting
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -26,6 +28,153 @@ pub struct Settings {
     pub rebuild_interval: u8,
     /// post count per index page
     pub posts_per_page: usize,
+    /// blog taxonomies, `tags` by default
+    pub taxonomies: Vec<TaxonomySettings>,
+    /// blog authors, `[authors.<id>]` tables
+    pub authors: BTreeMap<String, Author>,
+    /// blog default language, its posts are located at the site root
+    pub default_language: String,
+    /// blog other languages, `[languages.<lang>]` tables
+    pub languages: BTreeMap<String, LanguageSettings>,
+    /// add self-link anchors to post headings
+    pub heading_anchors: bool,
+    /// reading speed of non-CJK text, used by post reading time
+    pub words_per_minute: usize,
+    /// reading speed of CJK text, used by post reading time
+    pub cjk_chars_per_minute: usize,
+    /// blog cache directory, such as the rendered diagrams
+    pub cache_dir: String,
+    /// diagram render commands of fenced code block languages, empty command is disabled
+    pub diagrams: BTreeMap<String, String>,
+    /// responsive images of posts
+    pub images: ImageSettings,
+    /// export theme static files with content hash in file name, such as `static/main.3f9a1c2b.css`
+    pub fingerprint_assets: bool,
+    /// compute subresource integrity hashes of theme static files
+    pub asset_integrity: bool,
+    /// theme options, override the default options of theme `theme.toml`
+    pub theme_options: toml::Table,
+}
+
+/// blog language setting
+///
+/// the empty fields are the same as the blog settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageSettings {
+    /// localized blog site name
+    #[serde(default)]
+    pub site_name: String,
+    /// localized blog site motto
+    #[serde(default)]
+    pub site_motto: String,
+    /// localized blog footer note
+    #[serde(default)]
+    pub footer_note: String,
+}
+
+/// blog responsive image setting
+///
+/// declared in `config.toml`:
+///
+/// ```toml
+/// [images]
+/// enable = true
+/// widths = [480, 960, 1440]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSettings {
+    /// enable responsive images of posts
+    pub enable: bool,
+    /// widths of resized image variants, the widths not less than the image width are skipped
+    pub widths: Vec<u32>,
+    /// modern formats of image variants, `webp` is encodable
+    pub formats: Vec<String>,
+    /// `sizes` attribute of responsive images
+    pub sizes: String,
+    /// jpeg encoding quality
+    pub quality: u8,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        ImageSettings {
+            enable: false,
+            widths: vec![480, 960, 1440],
+            formats: vec![String::from("webp")],
+            sizes: String::from("(max-width: 960px) 100vw, 960px"),
+            quality: 80,
+        }
+    }
+}
+
+/// blog taxonomy setting
+///
+/// declared in `config.toml` as array of tables:
+///
+/// ```toml
+/// [[taxonomies]]
+/// name = "categories"
+/// hierarchical = true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxonomySettings {
+    /// taxonomy name, also the post header key, e.g. `tags: [hello, world]`
+    pub name: String,
+    /// url prefix of taxonomy pages, default is the taxonomy name
+    #[serde(default)]
+    pub path: String,
+    /// hierarchical flag, post of `rust/async` term is also rolled up to `rust` term
+    #[serde(default)]
+    pub hierarchical: bool,
+    /// post count per term page, default is the blog `posts_per_page`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub posts_per_page: Option<usize>,
+    /// generate atom feed for every term
+    #[serde(default)]
+    pub feed: bool,
+    /// template of taxonomy list page
+    #[serde(default = "default_list_template")]
+    pub list_template: String,
+    /// template of taxonomy term page
+    #[serde(default = "default_term_template")]
+    pub term_template: String,
+}
+
+impl TaxonomySettings {
+    /// the `authors` taxonomy, used when blog has authors but does not declare it
+    pub fn authors() -> TaxonomySettings {
+        TaxonomySettings {
+            name: String::from("authors"),
+            path: String::from("authors"),
+            hierarchical: false,
+            posts_per_page: None,
+            feed: true,
+            list_template: String::from("authors.tpl"),
+            term_template: String::from("author.tpl"),
+        }
+    }
+
+    /// the default `tags` taxonomy
+    pub fn tags() -> TaxonomySettings {
+        TaxonomySettings {
+            name: String::from("tags"),
+            path: String::from("tags"),
+            hierarchical: false,
+            posts_per_page: None,
+            feed: false,
+            list_template: default_list_template(),
+            term_template: default_term_template(),
+        }
+    }
+}
+
+fn default_list_template() -> String {
+    String::from("tags.tpl")
+}
+
+fn default_term_template() -> String {
+    String::from("tag.tpl")
 }
 
 impl Default for Settings {
@@ -41,10 +190,48 @@ impl Default for Settings {
             theme_root_dir: String::from("_themes"),
             rebuild_interval: 2,
             posts_per_page: 20,
+            taxonomies: vec![TaxonomySettings::tags()],
+            authors: BTreeMap::new(),
+            default_language: String::from("en"),
+            languages: BTreeMap::new(),
+            heading_anchors: false,
+            words_per_minute: 200,
+            cjk_chars_per_minute: 400,
+            cache_dir: String::from("_cache"),
+            diagrams: BTreeMap::from([
+                (String::from("dot"), String::from("dot -Tsvg")),
+                (
+                    String::from("mermaid"),
+                    String::from("mmdc --input - --output - --outputFormat svg --quiet"),
+                ),
+            ]),
+            images: ImageSettings::default(),
+            fingerprint_assets: false,
+            asset_integrity: false,
+            theme_options: toml::Table::new(),
         };
     }
 }
 
+impl Settings {
+    /// the settings localized to `lang` with `languages` overrides.
+    pub fn localize(&self, lang: &str) -> Settings {
+        let mut settings = self.clone();
+        if let Some(overrides) = self.languages.get(lang) {
+            if !overrides.site_name.is_empty() {
+                settings.site_name = overrides.site_name.clone();
+            }
+            if !overrides.site_motto.is_empty() {
+                settings.site_motto = overrides.site_motto.clone();
+            }
+            if !overrides.footer_note.is_empty() {
+                settings.footer_note = overrides.footer_note.clone();
+            }
+        }
+        settings
+    }
+}
+
 impl Source for Settings {
     fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
         Box::new((*self).clone())
@@ -0,0 +1,276 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::Arc;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use tera::{Context, Tera, Value};
+
+use crate::utils::markdown_to_fragment;
+
+/// the body variable name of block shortcode call
+const BODY_VAR: &str = "__shortcode_body";
+
+/// shortcode call found in post body
+struct Call<'a> {
+    /// byte range of the whole call in post body
+    range: Range<usize>,
+    /// shortcode name
+    name: &'a str,
+    /// tera style arguments, `id="xyz", autoplay=true`
+    args: &'a str,
+    /// the body of block shortcode
+    body: Option<&'a str>,
+}
+
+/// render the shortcodes of post `content` with shortcode `templates`.
+///
+/// inline shortcode `{{ name(key=value) }}` and block shortcode `{% name(key=value) %}...{% end %}`
+/// are rendered with the `name` template, the arguments are the template context, the block body is `body`.
+/// shortcodes in code blocks and code spans are untouched, so are the calls of unknown shortcode names.
+/// the error is returned with the byte offset of the failed shortcode.
+pub fn render_shortcodes(content: &str, templates: &Tera) -> Result<String, (usize, tera::Error)> {
+    let names: BTreeSet<&str> = templates.get_template_names().collect();
+    if names.is_empty() || !(content.contains("{{") || content.contains("{%")) {
+        return Ok(content.to_string());
+    }
+    let calls = find_calls(content, &names).map_err(|(offset, msg)| (offset, tera::Error::msg(msg)))?;
+    if calls.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let mut renderer = shortcode_renderer(templates);
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for call in calls {
+        output.push_str(&content[last..call.range.start]);
+        let mut context = Context::new();
+        let mut args = call.args.trim().to_string();
+        if let Some(body) = call.body {
+            context.insert(BODY_VAR, body.trim_matches('\n'));
+            if !args.is_empty() {
+                args.push_str(", ");
+            }
+            args.push_str(&format!("body={}", BODY_VAR));
+        }
+        let snippet = format!("{{{{ {}({}) }}}}", call.name, args);
+        let html = renderer
+            .render_str(&snippet, &context)
+            .map_err(|e| (call.range.start, e))?;
+        output.push_str(&html);
+        last = call.range.end;
+    }
+    output.push_str(&content[last..]);
+    Ok(output)
+}
+
+/// the renderer with every shortcode registered as a tera function.
+fn shortcode_renderer(templates: &Tera) -> Tera {
+    let shared = Arc::new(templates.clone());
+    let mut renderer = Tera::default();
+    for name in templates.get_template_names() {
+        let template_name = name.to_string();
+        let templates = shared.clone();
+        renderer.register_function(name, move |args: &HashMap<String, Value>| {
+            let context = Context::from_serialize(args)?;
+            templates.render(&template_name, &context).map(Value::String)
+        });
+    }
+    renderer
+}
+
+/// register `markdown` filter to shortcode templates, `{{ body | markdown | safe }}`.
+pub fn register_markdown_filter(templates: &mut Tera) {
+    templates.register_filter("markdown", |value: &Value, _: &HashMap<String, Value>| {
+        let content = tera::try_get_value!("markdown", "value", String, value);
+        Ok(Value::String(markdown_to_fragment(&content)))
+    });
+}
+
+/// the byte ranges of code blocks and code spans.
+fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut depth = 0;
+    for (event, range) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                if depth == 0 {
+                    ranges.push(range);
+                }
+                depth += 1;
+            }
+            Event::End(pulldown_cmark::TagEnd::CodeBlock) => depth -= 1,
+            Event::Code(_) if depth == 0 => ranges.push(range),
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// find the shortcode calls outside code, the error is the byte offset and the reason.
+fn find_calls<'a>(content: &'a str, names: &BTreeSet<&str>) -> Result<Vec<Call<'a>>, (usize, String)> {
+    let codes = code_ranges(content);
+    let in_code = |pos: usize| codes.iter().any(|r| r.contains(&pos));
+
+    let mut calls = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = content[pos..].find('{') {
+        let start = pos + found;
+        pos = start + 1;
+        if in_code(start) {
+            continue;
+        }
+        let rest = &content[start..];
+        let close = if rest.starts_with("{{") {
+            "}}"
+        } else if rest.starts_with("{%") {
+            "%}"
+        } else {
+            continue;
+        };
+        let (name, args, tag_end) = match parse_tag(&rest[2..], close) {
+            Some(tag) => tag,
+            None => continue,
+        };
+        if !names.contains(name) {
+            continue;
+        }
+        let mut end = start + 2 + tag_end;
+        let mut body = None;
+        if close == "%}" {
+            let (body_end, end_tag_end) = find_end_tag(content, end, &in_code)
+                .ok_or_else(|| (start, format!("shortcode `{}` is not closed by `{{% end %}}`", name)))?;
+            body = Some(&content[end..body_end]);
+            end = end_tag_end;
+        }
+        calls.push(Call {
+            range: start..end,
+            name,
+            args,
+            body,
+        });
+        pos = end;
+    }
+    Ok(calls)
+}
+
+/// parse ` name(args) }}` after the open delimiter, return the name, args and the end offset.
+fn parse_tag<'a>(s: &'a str, close: &str) -> Option<(&'a str, &'a str, usize)> {
+    let trimmed = s.trim_start_matches([' ', '\t']);
+    let name_start = s.len() - trimmed.len();
+    let name_len = trimmed
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(trimmed.len());
+    if name_len == 0 || !trimmed[name_len..].starts_with('(') {
+        return None;
+    }
+    let name = &s[name_start..name_start + name_len];
+    let args_start = name_start + name_len + 1;
+
+    let mut quote: Option<char> = None;
+    let mut args_end = None;
+    for (i, c) in s[args_start..].char_indices() {
+        match (quote, c) {
+            (_, '\n') => return None,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') | (None, '`') => quote = Some(c),
+            (None, ')') => {
+                args_end = Some(args_start + i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let args_end = args_end?;
+    let after = &s[args_end + 1..];
+    let tail = after.trim_start_matches([' ', '\t']);
+    if !tail.starts_with(close) {
+        return None;
+    }
+    let end = args_end + 1 + (after.len() - tail.len()) + close.len();
+    Some((name, &s[args_start..args_end], end))
+}
+
+/// find `{% end %}` after `from`, return the start and the end offset of the end tag.
+fn find_end_tag(content: &str, from: usize, in_code: &dyn Fn(usize) -> bool) -> Option<(usize, usize)> {
+    let mut pos = from;
+    while let Some(found) = content[pos..].find("{%") {
+        let start = pos + found;
+        pos = start + 2;
+        if in_code(start) {
+            continue;
+        }
+        let rest = content[pos..].trim_start_matches([' ', '\t']);
+        if let Some(rest) = rest.strip_prefix("end") {
+            let tail = rest.trim_start_matches([' ', '\t']);
+            if tail.starts_with("%}") {
+                return Some((start, content.len() - tail.len() + 2));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> Tera {
+        let mut templates = Tera::default();
+        templates
+            .add_raw_templates(vec![
+                (
+                    "youtube",
+                    r#"<iframe src="{{ id }}" title="{{ title | default(value='') }}"></iframe>"#,
+                ),
+                ("note", r#"<div class="note">{{ body | markdown | safe }}</div>"#),
+                ("fail", "{{ missing }}"),
+            ])
+            .unwrap();
+        register_markdown_filter(&mut templates);
+        templates
+    }
+
+    #[test]
+    fn parse_tag_args() {
+        let tag = r#" youtube(id="a)b", title='}}') }} tail"#;
+        let (name, args, end) = parse_tag(tag, "}}").unwrap();
+        assert_eq!(name, "youtube");
+        assert_eq!(args, r#"id="a)b", title='}}'"#);
+        assert_eq!(end, tag.find(" tail").unwrap());
+
+        assert_eq!(parse_tag(" note() %}", "%}"), Some(("note", "", 10)));
+        assert_eq!(parse_tag(" youtube }}", "}}"), None);
+        assert_eq!(parse_tag(" youtube(id=1) %}", "}}"), None);
+        assert_eq!(parse_tag(" youtube(id=\"a\n\") }}", "}}"), None);
+    }
+
+    #[test]
+    fn render_inline_and_block() {
+        let content = "a {{ youtube(id=\"xyz\", title=\"t\") }} b\n\n{% note() %}\n*hi*\n{% end %}\n";
+        let output = render_shortcodes(content, &templates()).unwrap();
+        assert_eq!(
+            output,
+            "a <iframe src=\"xyz\" title=\"t\"></iframe> b\n\n<div class=\"note\"><p><em>hi</em></p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn skip_code_and_unknown_names() {
+        let content =
+            "`{{ youtube(id=\"x\") }}`\n\n```\n{{ youtube(id=\"x\") }}\n```\n\n{{ other(id=\"x\") }} {{ title }}\n";
+        assert_eq!(render_shortcodes(content, &templates()).unwrap(), content);
+    }
+
+    #[test]
+    fn error_offsets() {
+        let content = "line 1\n\nline 3 {{ fail() }}\n";
+        let (offset, _) = render_shortcodes(content, &templates()).unwrap_err();
+        assert_eq!(offset, content.find("{{").unwrap());
+
+        let content = "line 1\n\n{% note() %}\nnever closed\n";
+        let (offset, e) = render_shortcodes(content, &templates()).unwrap_err();
+        assert_eq!(offset, content.find("{%").unwrap());
+        assert!(e.to_string().contains("is not closed"));
+    }
+}
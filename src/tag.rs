@@ -5,6 +5,8 @@ use serde::Serialize;
 use crate::post::Post;
 
 /// blog tag
+///
+/// a term of a taxonomy, `tags` or others declared in settings.
 #[derive(Serialize)]
 pub struct Tag {
     /// tag name
@@ -13,6 +15,10 @@ pub struct Tag {
     pub num: isize,
     /// the posts
     pub posts: Vec<Rc<Post>>,
+    /// parent tag name of hierarchical taxonomy, `rust` is the parent of `rust/async`
+    pub parent: Option<String>,
+    /// children tag names of hierarchical taxonomy
+    pub children: Vec<String>,
 }
 
 impl Tag {
@@ -22,11 +28,18 @@ impl Tag {
             name: name.to_string(),
             num: 0,
             posts: Vec::new(),
+            parent: None,
+            children: Vec::new(),
         }
     }
 
     /// add a post to `Tag`
+    ///
+    /// the post is ignored if it has already been added.
     pub fn add(&mut self, post: Rc<Post>) {
+        if self.posts.iter().any(|p| Rc::ptr_eq(p, &post)) {
+            return;
+        }
         self.num += 1;
         self.posts.push(post);
     }
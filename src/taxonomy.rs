@@ -0,0 +1,148 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::page::Page;
+use crate::post::Post;
+use crate::settings::TaxonomySettings;
+use crate::tag::Tag;
+
+/// blog taxonomy
+///
+/// a classification of posts, such as `tags` or `categories`,
+/// every taxonomy has its own terms and term pages.
+#[derive(Serialize)]
+pub struct Taxonomy {
+    /// taxonomy settings
+    pub settings: TaxonomySettings,
+    /// taxonomy terms map
+    pub terms: BTreeMap<String, Tag>,
+    /// taxonomy term pages
+    pub pages: BTreeMap<String, Vec<Rc<Page>>>,
+}
+
+impl Taxonomy {
+    /// create new `Taxonomy`
+    pub fn new(settings: &TaxonomySettings) -> Taxonomy {
+        Taxonomy {
+            settings: settings.clone(),
+            terms: BTreeMap::new(),
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// taxonomy name
+    pub fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    /// add a post to the `term` of taxonomy.
+    ///
+    /// for hierarchical taxonomy, the post is also added to all ancestors of `term`.
+    pub fn add(&mut self, term: &str, post: Rc<Post>) {
+        if !self.settings.hierarchical {
+            let tag = self.terms.entry(term.to_string()).or_insert(Tag::new(term));
+            tag.add(post);
+            return;
+        }
+        let segments: Vec<&str> = term.split('/').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let mut parent: Option<String> = None;
+        for i in 1..=segments.len() {
+            let name = segments[..i].join("/");
+            let tag = self.terms.entry(name.clone()).or_insert(Tag::new(&name));
+            tag.parent = parent.clone();
+            tag.add(post.clone());
+            if let Some(ref parent) = parent {
+                let parent_tag = self.terms.get_mut(parent).expect("taxonomy parent term missing");
+                if !parent_tag.children.contains(&name) {
+                    parent_tag.children.push(name.clone());
+                }
+            }
+            parent = Some(name);
+        }
+    }
+
+    /// sort term posts by created time, newest first.
+    pub fn sort_posts(&mut self) {
+        for tag in self.terms.values_mut() {
+            tag.posts.sort_by_key(|p| Reverse(p.headers.created));
+        }
+    }
+
+    /// build term pages, `posts_per_page` is used if taxonomy does not set its own.
+    pub fn build_pages(&mut self, posts_per_page: usize) {
+        let per_page = self.settings.posts_per_page.unwrap_or(posts_per_page);
+        self.pages = self
+            .terms
+            .values()
+            .map(|tag| (tag.name.clone(), Page::paginate(&tag.name, &tag.posts, per_page)))
+            .collect();
+    }
+
+    /// taxonomy terms sorted by case insensitive name.
+    pub fn sorted_terms(&self) -> Vec<&Tag> {
+        let mut terms = self.terms.values().collect::<Vec<_>>();
+        terms.sort_by_key(|x| x.name.to_lowercase());
+        terms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn post(root: &Path, name: &str) -> Rc<Post> {
+        let path = Path::new("posts").join(name);
+        std::fs::create_dir_all(root.join("posts")).unwrap();
+        std::fs::write(root.join(&path), "created: 2024-01-01T00:00:00Z\n\nhello\n").unwrap();
+        Rc::new(Post::new(root, &path, &[], "en").unwrap())
+    }
+
+    fn names(posts: &[Rc<Post>]) -> Vec<&str> {
+        posts.iter().map(|p| p.title.as_str()).collect()
+    }
+
+    #[test]
+    fn hierarchical_rollup() {
+        let root = tempfile::tempdir().unwrap();
+        let settings: TaxonomySettings = toml::from_str("name = \"categories\"\nhierarchical = true").unwrap();
+        let mut taxonomy = Taxonomy::new(&settings);
+        let a = post(root.path(), "a.md");
+        let b = post(root.path(), "b.md");
+        taxonomy.add("rust/async", a.clone());
+        taxonomy.add("rust", a.clone());
+        taxonomy.add(" rust / web ", b.clone());
+
+        let terms: Vec<&str> = taxonomy.terms.keys().map(|x| x.as_str()).collect();
+        assert_eq!(terms, ["rust", "rust/async", "rust/web"]);
+        let rust = &taxonomy.terms["rust"];
+        assert_eq!(rust.num, 2);
+        assert_eq!(names(&rust.posts), ["a", "b"]);
+        assert_eq!(rust.parent, None);
+        assert_eq!(rust.children, ["rust/async", "rust/web"]);
+        let rust_async = &taxonomy.terms["rust/async"];
+        assert_eq!(rust_async.num, 1);
+        assert_eq!(names(&rust_async.posts), ["a"]);
+        assert_eq!(rust_async.parent.as_deref(), Some("rust"));
+        assert!(rust_async.children.is_empty());
+    }
+
+    #[test]
+    fn flat_terms() {
+        let root = tempfile::tempdir().unwrap();
+        let settings: TaxonomySettings = toml::from_str("name = \"tags\"").unwrap();
+        let mut taxonomy = Taxonomy::new(&settings);
+        let a = post(root.path(), "a.md");
+        taxonomy.add("rust/async", a.clone());
+        taxonomy.add("rust/async", a);
+
+        let terms: Vec<&str> = taxonomy.terms.keys().map(|x| x.as_str()).collect();
+        assert_eq!(terms, ["rust/async"]);
+        assert_eq!(taxonomy.terms["rust/async"].num, 1);
+        assert_eq!(taxonomy.terms["rust/async"].parent, None);
+    }
+}
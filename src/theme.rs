@@ -1,10 +1,22 @@
+use std::borrow::Cow;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
 use std::path::{Path, PathBuf};
 
-use tera::Tera;
+use glob::Pattern;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera};
+use toml::{Table, Value};
 use tracing::{debug, info};
+use walkdir::WalkDir;
 
+use crate::assets::{AssetManifest, ASSET_MANIFEST};
 use crate::error::{Error, Result};
-use crate::utils::{read_file, write_file};
+use crate::post::Post;
+use crate::shortcode::register_markdown_filter;
+use crate::utils::{is_hidden, read_file, write_file};
 
 macro_rules! try_init_template {
     ($render:expr, $tpl_name:expr, $tpl_str:expr) => {
@@ -25,31 +37,140 @@ macro_rules! try_read_file {
     };
 }
 
-macro_rules! try_write_file {
-    ($src_dir: expr, $dest_dir: expr, $p: expr, $buf: expr) => {
-        let p = $src_dir.join($p);
-        if p.exists() {
-            write_file(&$dest_dir.join($p), $buf)?;
+/// theme manifest, `theme.toml` file of theme directory
+///
+/// ```toml
+/// name = "mytheme"
+/// version = "0.1.0"
+/// description = "my blog theme"
+/// parent = "simple"
+/// mdblog_version = ">=0.22"
+///
+/// [options]
+/// show_author = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeManifest {
+    /// theme name
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// theme version
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    /// theme description
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// parent theme name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// required mdblog version, such as `>=0.22`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mdblog_version: Option<VersionReq>,
+    /// default theme options, overridden by `[theme_options]` of blog `config.toml`
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub options: Table,
+    /// post extra headers schema
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, ExtraHeader>,
+}
+
+/// value type of post extra header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtraHeaderType {
+    /// any value
+    #[default]
+    Any,
+    /// string value
+    String,
+    /// integer value
+    Integer,
+    /// integer or float value
+    Float,
+    /// boolean value
+    Boolean,
+    /// array value
+    Array,
+    /// table value
+    Table,
+}
+
+/// theme declaration of post extra header
+///
+/// declared in theme `theme.toml`:
+///
+/// ```toml
+/// [extra.cover_image]
+/// type = "string"
+/// required = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtraHeader {
+    /// header value type
+    #[serde(rename = "type", default)]
+    pub kind: ExtraHeaderType,
+    /// header required flag
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl ExtraHeader {
+    /// check the header `value`, return the error reason if it is invalid.
+    pub fn check(&self, value: Option<&serde_yaml::Value>) -> std::result::Result<(), String> {
+        use serde_yaml::Value;
+
+        let value = match value {
+            None if self.required => return Err("is required".to_string()),
+            None => return Ok(()),
+            Some(value) => value,
+        };
+        let valid = match self.kind {
+            ExtraHeaderType::Any => true,
+            ExtraHeaderType::String => value.is_string(),
+            ExtraHeaderType::Integer => value.is_i64() || value.is_u64(),
+            ExtraHeaderType::Float => value.is_number(),
+            ExtraHeaderType::Boolean => value.is_bool(),
+            ExtraHeaderType::Array => value.is_sequence(),
+            ExtraHeaderType::Table => matches!(value, Value::Mapping(_)),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("must be {:?} type", self.kind).to_lowercase())
         }
-    };
+    }
 }
 
 /// blog theme object
+///
+/// all files of theme `templates` directory are loaded, such as `partials/nav.tpl`.
+/// a theme can declare its parent theme in `theme.toml`, the missing templates and static files
+/// are resolved from the parent chain down to the embedded simple theme.
+/// the embedded themes, such as `docs` and `dark`, are used if their directories do not exist.
 #[derive(Default)]
 pub struct Theme {
     /// theme root directory
     pub root: PathBuf,
     /// theme name
     pub name: String,
+    /// parent theme names, from the nearest one
+    pub parents: Vec<String>,
+    /// theme manifest, declared in theme `theme.toml`
+    pub manifest: ThemeManifest,
+    /// default theme options, merged from the parent chain
+    pub options: Table,
     /// theme renderer
     pub renderer: Tera,
-    main_css: Vec<u8>,
-    atom: Vec<u8>,
-    base: Vec<u8>,
-    index: Vec<u8>,
-    post: Vec<u8>,
-    tag: Vec<u8>,
-    tags: Vec<u8>,
+    /// post body shortcodes, templates of theme `shortcodes` directory
+    pub shortcodes: Tera,
+    /// post extra headers schema, declared in theme `theme.toml`
+    pub extra_headers: BTreeMap<String, ExtraHeader>,
+    /// theme layers of the inheritance chain, from the theme itself
+    layers: Vec<ThemeLayer>,
+    /// template contents keyed by template name
+    templates: BTreeMap<String, Vec<u8>>,
+    /// template files keyed by template name, used by error reports
+    sources: BTreeMap<String, String>,
 }
 
 impl Theme {
@@ -63,41 +184,194 @@ impl Theme {
             renderer: Tera::default(),
             ..Default::default()
         };
-        let src_dir = root.join(name);
-        if !src_dir.exists() {
-            if name != "simple" {
-                return Err(Error::ThemeNotFound(name.into()));
+        let mut manifests = Vec::new();
+        let mut names = Vec::new();
+        let mut next = Some(name.to_string());
+        while let Some(current) = next.take() {
+            if names.contains(&current) {
+                return Err(Error::ThemeParentCycle(current));
             }
-            theme.main_css.extend_from_slice(SIMPLE_MAIN_CSS);
-            theme.atom.extend_from_slice(SIMPLE_ATOM);
-            theme.base.extend_from_slice(SIMPLE_BASE);
-            theme.index.extend_from_slice(SIMPLE_INDEX);
-            theme.post.extend_from_slice(SIMPLE_POST);
-            theme.tag.extend_from_slice(SIMPLE_TAG);
-            theme.tags.extend_from_slice(SIMPLE_TAGS);
-            theme.init_template()?;
-            return Ok(theme);
-        }
-
-        try_read_file!(src_dir, "static/main.css", &mut theme.main_css);
-        read_file(&src_dir.join("templates/atom.tpl"), &mut theme.atom)?;
-        read_file(&src_dir.join("templates/base.tpl"), &mut theme.base)?;
-        read_file(&src_dir.join("templates/index.tpl"), &mut theme.index)?;
-        read_file(&src_dir.join("templates/post.tpl"), &mut theme.post)?;
-        read_file(&src_dir.join("templates/tag.tpl"), &mut theme.tag)?;
-        read_file(&src_dir.join("templates/tags.tpl"), &mut theme.tags)?;
+            names.push(current.clone());
+            let src_dir = root.join(&current);
+            let (layer, manifest) = if src_dir.exists() {
+                let manifest = ThemeManifest::load(&src_dir, &current)?;
+                (ThemeLayer::Dir(src_dir), manifest)
+            } else {
+                match BuiltinTheme::get(&current) {
+                    Some(builtin) => (ThemeLayer::Builtin(builtin), builtin.manifest()),
+                    None => return Err(Error::ThemeNotFound(current)),
+                }
+            };
+            manifest.check_version(&current)?;
+            next = manifest.parent.clone();
+            theme.layers.push(layer);
+            manifests.push(manifest);
+        }
+        if let Some(ThemeLayer::Dir(_)) = theme.layers.last() {
+            let simple = BuiltinTheme::get("simple").expect("simple theme error");
+            theme.layers.push(ThemeLayer::Builtin(simple));
+            manifests.push(simple.manifest());
+        }
+        theme.parents = names.split_off(1);
+        theme.manifest = manifests[0].clone();
+
+        register_markdown_filter(&mut theme.shortcodes);
+        for (layer, manifest) in theme.layers.clone().iter().zip(manifests).rev() {
+            merge_options(&mut theme.options, &manifest.options);
+            theme.extra_headers.extend(manifest.extra);
+            match layer {
+                ThemeLayer::Dir(src_dir) => theme.read_templates(&src_dir.join("templates"))?,
+                ThemeLayer::Builtin(builtin) => {
+                    for (name, content) in builtin.templates {
+                        theme.templates.insert(name.to_string(), content.to_vec());
+                        let source = format!("<{}>/templates/{}", builtin.name, name);
+                        theme.sources.insert(name.to_string(), source);
+                    }
+                }
+            }
+        }
         theme.init_template()?;
+        theme.init_shortcodes()?;
         return Ok(theme);
     }
 
     /// init renderer template.
     fn init_template(&mut self) -> Result<()> {
-        try_init_template!(self.renderer, "atom.tpl", self.atom);
-        try_init_template!(self.renderer, "base.tpl", self.base);
-        try_init_template!(self.renderer, "index.tpl", self.index);
-        try_init_template!(self.renderer, "post.tpl", self.post);
-        try_init_template!(self.renderer, "tag.tpl", self.tag);
-        try_init_template!(self.renderer, "tags.tpl", self.tags);
+        let mut templates = Vec::new();
+        for (name, content) in &self.templates {
+            match std::str::from_utf8(content) {
+                Ok(content) => templates.push((name.as_str(), content)),
+                Err(_) => return Err(Error::ThemeFileEncoding(name.clone())),
+            }
+        }
+        if let Err(e) = self.renderer.add_raw_templates(templates) {
+            return Err(self.template_error(e));
+        }
+        Ok(())
+    }
+
+    /// render the template `name` with `context`.
+    pub fn render(&self, name: &str, context: &Context) -> Result<String> {
+        self.renderer.render(name, context).map_err(|e| self.template_error(e))
+    }
+
+    /// the template error `e` with the template file and line.
+    ///
+    /// the template is the one named by `(error happened in '...')` of an extended template,
+    /// or else the one quoted in the innermost message which mentions a theme template,
+    /// the line is from the parse error position ` --> line:column`.
+    fn template_error(&self, e: tera::Error) -> Error {
+        let mut messages = Vec::new();
+        let mut source: Option<&dyn StdError> = Some(&e);
+        while let Some(err) = source {
+            messages.push(err.to_string());
+            source = err.source();
+        }
+        let quoted = |text: &str| {
+            text.split('\'')
+                .skip(1)
+                .step_by(2)
+                .filter(|x| self.sources.contains_key(*x))
+                .last()
+                .map(|x| x.to_string())
+        };
+        let name = messages
+            .iter()
+            .find_map(|message| quoted(message.split("(error happened in ").nth(1)?))
+            .or_else(|| messages.iter().rev().find_map(|message| quoted(message)));
+        let mut location = match name {
+            Some(name) => self.sources[&name].clone(),
+            None => return Error::Template(e),
+        };
+        let line = messages.iter().find_map(|message| {
+            let start = message.find("--> ")? + 4;
+            let position = &message[start..];
+            position[..position.find(':')?].trim().parse::<usize>().ok()
+        });
+        if let Some(line) = line {
+            location.push_str(&format!(":{}", line));
+        }
+        Error::ThemeTemplate(e, location)
+    }
+
+    /// read all template files of theme `templates` directory, the template name is the relative path.
+    fn read_templates(&mut self, dir: &Path) -> Result<()> {
+        let pattern = format!("{}/**/*", Pattern::escape(&dir.to_string_lossy()));
+        for path in glob::glob(&pattern)? {
+            let path = path.map_err(|e| e.into_error())?;
+            let rel_path: Vec<_> = path.strip_prefix(dir)?.iter().map(|x| x.to_string_lossy()).collect();
+            if !path.is_file() || rel_path.iter().any(|x| x.starts_with('.')) {
+                continue;
+            }
+            let mut content = Vec::new();
+            read_file(&path, &mut content)?;
+            self.templates.insert(rel_path.join("/"), content);
+            self.sources.insert(rel_path.join("/"), path.display().to_string());
+        }
+        Ok(())
+    }
+
+    /// init shortcode templates, the shortcode name is the template file stem.
+    fn init_shortcodes(&mut self) -> Result<()> {
+        for (name, (_, content)) in self.shortcode_files()? {
+            try_init_template!(self.shortcodes, &name, content);
+        }
+        Ok(())
+    }
+
+    /// shortcode files of theme `shortcodes` directories keyed by shortcode name, the value is file name and content.
+    ///
+    /// the shortcodes of the theme override the ones of its parents.
+    fn shortcode_files(&self) -> Result<BTreeMap<String, (String, Vec<u8>)>> {
+        let mut files = BTreeMap::new();
+        for layer in &self.layers {
+            let dir = match layer {
+                ThemeLayer::Dir(src_dir) => src_dir.join("shortcodes"),
+                ThemeLayer::Builtin(_) => continue,
+            };
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut paths = Vec::new();
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let hidden = path.file_name().and_then(|x| x.to_str()).map(|x| x.starts_with('.'));
+                if path.is_file() && hidden == Some(false) {
+                    paths.push(path);
+                }
+            }
+            paths.sort();
+            for path in paths {
+                let name = path.file_stem().and_then(|x| x.to_str()).unwrap_or_default();
+                if let Entry::Vacant(file) = files.entry(name.to_string()) {
+                    let file_name = path.file_name().and_then(|x| x.to_str()).unwrap_or_default();
+                    let mut content = Vec::new();
+                    read_file(&path, &mut content)?;
+                    file.insert((file_name.to_string(), content));
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// the theme options overridden by `overrides`, such as `[theme_options]` of blog `config.toml`.
+    pub fn options(&self, overrides: &Table) -> Table {
+        let mut options = self.options.clone();
+        merge_options(&mut options, overrides);
+        options
+    }
+
+    /// validate post headers with theme, the extra headers schema and the `template` header.
+    pub fn check_headers(&self, post: &Post) -> Result<()> {
+        let template = &post.headers.template;
+        if !template.is_empty() && !self.templates.contains_key(template) {
+            return Err(Error::PostTemplateNotFound(post.path.clone(), template.clone()));
+        }
+        for (key, schema) in &self.extra_headers {
+            if let Err(reason) = schema.check(post.headers.extra.get(key)) {
+                return Err(Error::PostExtraHeaderInvalid(post.path.clone(), key.clone(), reason));
+            }
+        }
         Ok(())
     }
 
@@ -109,30 +383,240 @@ impl Theme {
             return Ok(());
         }
         debug!("init theme({}) ...", name);
-        write_file(&dest_dir.join("static/main.css"), &self.main_css)?;
-        write_file(&dest_dir.join("templates/atom.tpl"), &self.atom)?;
-        write_file(&dest_dir.join("templates/base.tpl"), &self.base)?;
-        write_file(&dest_dir.join("templates/index.tpl"), &self.index)?;
-        write_file(&dest_dir.join("templates/post.tpl"), &self.post)?;
-        write_file(&dest_dir.join("templates/tag.tpl"), &self.tag)?;
-        write_file(&dest_dir.join("templates/tags.tpl"), &self.tags)?;
+        let manifest = ThemeManifest {
+            name: name.to_string(),
+            ..self.manifest.clone()
+        };
+        let manifest = toml::to_string(&manifest)?;
+        write_file(&dest_dir.join("theme.toml"), manifest.as_bytes())?;
+        for (rel_path, content) in self.static_files()? {
+            write_file(&dest_dir.join(rel_path), &content)?;
+        }
+        for (name, content) in &self.templates {
+            write_file(&dest_dir.join("templates").join(name), content)?;
+        }
+        for (file_name, content) in self.shortcode_files()?.values() {
+            write_file(&dest_dir.join("shortcodes").join(file_name), content)?;
+        }
         Ok(())
     }
 
-    /// export theme static files.
-    pub fn export_static<P: AsRef<Path>>(&self, root: P) -> Result<()> {
+    /// export theme `static` directories of the inheritance chain, fingerprinted by `assets` if enabled.
+    pub fn export_static<P: AsRef<Path>>(&self, root: P, assets: &mut AssetManifest) -> Result<()> {
         debug!("exporting theme({}) static ...", self.name);
-        let src_dir = self.root.join(&self.name);
         let dest_dir = root.as_ref();
-        try_write_file!(src_dir, dest_dir, "static/main.css", &self.main_css);
+        for (rel_path, content) in self.static_files()? {
+            assets.write(dest_dir, &rel_path, &content)?;
+        }
+        assets.write_manifest(dest_dir)?;
         Ok(())
     }
+
+    /// the files of theme `static` directories of the inheritance chain, keyed by path relative to the theme directory.
+    ///
+    /// the files of the theme override the ones of its parents,
+    /// the embedded themes provide their built-in `static/main.css`,
+    /// and the file at `ASSET_MANIFEST` path is skipped for the generated asset manifest.
+    fn static_files(&self) -> Result<BTreeMap<String, Cow<'_, [u8]>>> {
+        let mut files: BTreeMap<String, Cow<[u8]>> = BTreeMap::new();
+        for layer in &self.layers {
+            let src_dir = match layer {
+                ThemeLayer::Dir(src_dir) => src_dir,
+                ThemeLayer::Builtin(builtin) => {
+                    files
+                        .entry("static/main.css".to_string())
+                        .or_insert(Cow::Borrowed(builtin.main_css));
+                    continue;
+                }
+            };
+            let static_dir = src_dir.join("static");
+            if !static_dir.is_dir() {
+                continue;
+            }
+            let walker = WalkDir::new(&static_dir).into_iter();
+            for entry in walker.filter_entry(|e| !is_hidden(e)) {
+                let entry = entry.expect("get walker entry error");
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let rel_path = entry.path().strip_prefix(src_dir)?;
+                let rel_path: Vec<_> = rel_path.iter().map(|x| x.to_string_lossy()).collect();
+                let rel_path = rel_path.join("/");
+                if rel_path == ASSET_MANIFEST {
+                    continue;
+                }
+                if let Entry::Vacant(file) = files.entry(rel_path) {
+                    let mut content = Vec::new();
+                    read_file(entry.path(), &mut content)?;
+                    file.insert(Cow::Owned(content));
+                }
+            }
+        }
+        Ok(files)
+    }
 }
 
-static SIMPLE_MAIN_CSS: &[u8] = include_bytes!("themes/simple/static/main.css");
-static SIMPLE_ATOM: &[u8] = include_bytes!("themes/simple/templates/atom.tpl");
-static SIMPLE_BASE: &[u8] = include_bytes!("themes/simple/templates/base.tpl");
-static SIMPLE_INDEX: &[u8] = include_bytes!("themes/simple/templates/index.tpl");
-static SIMPLE_POST: &[u8] = include_bytes!("themes/simple/templates/post.tpl");
-static SIMPLE_TAG: &[u8] = include_bytes!("themes/simple/templates/tag.tpl");
-static SIMPLE_TAGS: &[u8] = include_bytes!("themes/simple/templates/tags.tpl");
+/// theme layer of the inheritance chain
+#[derive(Clone)]
+enum ThemeLayer {
+    /// theme directory
+    Dir(PathBuf),
+    /// embedded theme
+    Builtin(&'static BuiltinTheme),
+}
+
+/// embedded theme
+struct BuiltinTheme {
+    /// theme name
+    name: &'static str,
+    /// theme `theme.toml` content
+    manifest: &'static str,
+    /// theme `static/main.css` content
+    main_css: &'static [u8],
+    /// theme templates, the missing ones are inherited from the parent theme
+    templates: &'static [(&'static str, &'static [u8])],
+}
+
+impl BuiltinTheme {
+    /// get the embedded theme of `name`.
+    fn get(name: &str) -> Option<&'static BuiltinTheme> {
+        BUILTIN_THEMES.iter().find(|x| x.name == name)
+    }
+
+    /// the theme manifest.
+    fn manifest(&self) -> ThemeManifest {
+        toml::from_str(self.manifest).expect("embedded theme manifest error")
+    }
+}
+
+impl ThemeManifest {
+    /// load `theme.toml` of theme `src_dir`, the default manifest if it does not exist.
+    pub fn load(src_dir: &Path, name: &str) -> Result<ThemeManifest> {
+        let mut manifest = Vec::new();
+        try_read_file!(src_dir, "theme.toml", &mut manifest);
+        if manifest.is_empty() {
+            return Ok(ThemeManifest::default());
+        }
+        let manifest = String::from_utf8(manifest).map_err(|_| Error::ThemeFileEncoding("theme.toml".into()))?;
+        toml::from_str(&manifest).map_err(|e| Error::ThemeConfigParse(Box::new(e), name.into()))
+    }
+
+    /// the manifests of the embedded themes.
+    pub fn builtins() -> Vec<ThemeManifest> {
+        BUILTIN_THEMES.iter().map(|x| x.manifest()).collect()
+    }
+
+    /// check the required mdblog version.
+    fn check_version(&self, name: &str) -> Result<()> {
+        let required = match self.mdblog_version {
+            Some(ref required) => required,
+            None => return Ok(()),
+        };
+        let current = Version::parse(env!("CARGO_PKG_VERSION")).expect("mdblog version error");
+        if !required.matches(&current) {
+            return Err(Error::ThemeVersionUnsupported(name.into(), required.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// merge the `overrides` options into `options`, the tables are merged recursively.
+fn merge_options(options: &mut Table, overrides: &Table) {
+    for (key, value) in overrides {
+        match (options.get_mut(key), value) {
+            (Some(Value::Table(options)), Value::Table(overrides)) => merge_options(options, overrides),
+            _ => {
+                options.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// the embedded themes
+static BUILTIN_THEMES: [BuiltinTheme; 3] = [
+    BuiltinTheme {
+        name: "simple",
+        manifest: include_str!("themes/simple/theme.toml"),
+        main_css: include_bytes!("themes/simple/static/main.css"),
+        templates: &[
+            ("atom.tpl", include_bytes!("themes/simple/templates/atom.tpl")),
+            ("base.tpl", include_bytes!("themes/simple/templates/base.tpl")),
+            ("index.tpl", include_bytes!("themes/simple/templates/index.tpl")),
+            ("post.tpl", include_bytes!("themes/simple/templates/post.tpl")),
+            ("tag.tpl", include_bytes!("themes/simple/templates/tag.tpl")),
+            ("tags.tpl", include_bytes!("themes/simple/templates/tags.tpl")),
+            ("author.tpl", include_bytes!("themes/simple/templates/author.tpl")),
+            ("authors.tpl", include_bytes!("themes/simple/templates/authors.tpl")),
+        ],
+    },
+    BuiltinTheme {
+        name: "docs",
+        manifest: include_str!("themes/docs/theme.toml"),
+        main_css: concat!(
+            include_str!("themes/simple/static/main.css"),
+            "\n",
+            include_str!("themes/docs/static/main.css")
+        )
+        .as_bytes(),
+        templates: &[("base.tpl", include_bytes!("themes/docs/templates/base.tpl"))],
+    },
+    BuiltinTheme {
+        name: "dark",
+        manifest: include_str!("themes/dark/theme.toml"),
+        main_css: concat!(
+            include_str!("themes/simple/static/main.css"),
+            "\n",
+            include_str!("themes/dark/static/main.css")
+        )
+        .as_bytes(),
+        templates: &[],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// load theme `test` with `templates`, return the template file location of the error
+    /// of loading the theme, or of rendering the template `render`.
+    fn error_location(templates: &[(&str, &str)], render: &str) -> String {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("test/templates");
+        for (name, content) in templates {
+            write_file(&dir.join(name), content.as_bytes()).unwrap();
+        }
+        let result = Theme::new(root.path(), "test").and_then(|theme| theme.render(render, &Context::new()));
+        let location = match result {
+            Err(Error::ThemeTemplate(_, location)) => location,
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        };
+        location.replace(&dir.display().to_string(), "")
+    }
+
+    #[test]
+    fn template_parse_error() {
+        let location = error_location(&[("page.tpl", "hello\n{{ missing ")], "page.tpl");
+        assert_eq!(location, "/page.tpl:2");
+    }
+
+    #[test]
+    fn template_extends_error() {
+        let templates = [
+            ("layout.tpl", "{{ missing.title }}{% block body %}{% endblock %}"),
+            (
+                "page.tpl",
+                "{% extends \"layout.tpl\" %}{% block body %}hello{% endblock %}",
+            ),
+        ];
+        assert_eq!(error_location(&templates, "page.tpl"), "/layout.tpl");
+    }
+
+    #[test]
+    fn template_include_error() {
+        let templates = [
+            ("page.tpl", "hello\n{% include \"partials/footer.tpl\" %}"),
+            ("partials/footer.tpl", "{{ missing.footer }}"),
+        ];
+        assert_eq!(error_location(&templates, "page.tpl"), "/partials/footer.tpl");
+    }
+}
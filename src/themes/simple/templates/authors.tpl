@@ -0,0 +1,39 @@
+{% extends "base.tpl" %}
+
+{% block title %}
+  <title>Authors</title>
+{% endblock title %}
+
+{%- block css %}
+<style>
+  article section {
+    margin: 1rem 0;
+  }
+
+  article img {
+    width: 2rem;
+    height: 2rem;
+    border-radius: 50%;
+    vertical-align: middle;
+  }
+</style>
+{% endblock css -%}
+
+{% block main %}
+  <h1>Authors</h1>
+  <article>
+  {%- for author in authors %}
+    <section>
+      {%- if author.avatar %}
+      <img src="{{ author.avatar }}" alt="{{ author.name }}">
+      {%- endif %}
+      <a href="{{ lang_url }}/{{ taxonomy.settings.path }}/{{ author.id | urlencode }}.html">{{ author.name }}</a><sup>{{ taxonomy.terms | get(key=author.id) | get(key="num") }}</sup>
+      {%- if author.bio %}
+      <p>{{ author.bio }}</p>
+      {%- endif %}
+    </section>
+  {%- endfor %}
+  </article>
+{%- endblock main %}
+
+{% block js %}{% endblock js %}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+/// table of contents item of blog post
+#[derive(Debug, Clone, Serialize)]
+pub struct TocItem {
+    /// heading level, from 1 to 6
+    pub level: u32,
+    /// heading id, the anchor of `#id` link
+    pub id: String,
+    /// heading plain text title
+    pub title: String,
+    /// sub headings
+    pub children: Vec<TocItem>,
+}
+
+impl TocItem {
+    /// build toc tree from flat headings, lower level headings are nested in the previous higher one.
+    pub fn build_tree(headings: Vec<TocItem>) -> Vec<TocItem> {
+        let mut tree: Vec<TocItem> = Vec::new();
+        for heading in headings {
+            Self::insert(&mut tree, heading);
+        }
+        tree
+    }
+
+    fn insert(items: &mut Vec<TocItem>, heading: TocItem) {
+        match items.last_mut() {
+            Some(last) if last.level < heading.level => Self::insert(&mut last.children, heading),
+            _ => items.push(heading),
+        }
+    }
+}
+
+/// heading id generator, the ids are slugified and deduplicated.
+#[derive(Default)]
+pub struct Slugger {
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    /// get a unique slug of `title`, `-1`, `-2`... suffix is appended for duplicated slugs.
+    pub fn slug(&mut self, title: &str) -> String {
+        let base = slugify(title);
+        let mut slug = base.clone();
+        let mut i = 1;
+        while self.used.contains(&slug) {
+            slug = format!("{}-{}", base, i);
+            i += 1;
+        }
+        self.used.insert(slug.clone());
+        slug
+    }
+}
+
+/// slugify `title`, keep alphanumeric chars (including CJK), replace whitespaces with `-`.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        String::from("section")
+    } else {
+        slug.to_string()
+    }
+}
@@ -1,12 +1,18 @@
+use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, BlockQuoteKind, Event, Options, Parser, Tag, TagEnd};
 use tracing::error;
+use walkdir::DirEntry;
 
+use crate::diagram::DiagramRenderer;
 use crate::error::Result;
+use crate::settings::Settings;
+use crate::toc::{Slugger, TocItem};
 
 /// create the file of `path` and append content
 ///
@@ -27,17 +33,435 @@ pub fn read_file<P: AsRef<Path>>(path: P, buf: &mut Vec<u8>) -> Result<()> {
     Ok(())
 }
 
-/// the rendered html content of post body port
-pub fn markdown_to_html(content: &str) -> String {
+/// check directory entry is a hidden file.
+pub fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+}
+
+/// the excerpt marker of post body
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+/// the markdown options of post body
+fn markdown_options() -> Options {
     let mut opts = Options::empty();
     opts.insert(Options::ENABLE_TABLES);
     opts.insert(Options::ENABLE_FOOTNOTES);
     opts.insert(Options::ENABLE_TASKLISTS);
     opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_GFM);
+    opts
+}
+
+/// the admonition kinds of GitHub style alert blockquote, `> [!NOTE]`
+fn admonition_kind(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Note => "note",
+        BlockQuoteKind::Tip => "tip",
+        BlockQuoteKind::Important => "important",
+        BlockQuoteKind::Warning => "warning",
+        BlockQuoteKind::Caution => "caution",
+    }
+}
+
+/// the opening html of admonition block.
+fn admonition_start(kind: &str, title: &str) -> String {
+    let mut html = format!(r#"<div class="admonition admonition-{}">"#, kind);
+    html.push_str("\n<p class=\"admonition-title\">");
+    html.push_str(&tera::escape_html(title));
+    html.push_str("</p>\n");
+    html
+}
+
+/// the default title of admonition `kind`, `warning` -> `Warning`
+fn admonition_title(kind: &str) -> String {
+    let mut chars = kind.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// render GitHub style alert blockquotes to admonition blocks.
+fn render_admonitions(events: Vec<Event>) -> Vec<Event> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut stack: Vec<bool> = Vec::new();
+    for event in events {
+        match event {
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let kind = admonition_kind(kind);
+                output.push(Event::Html(admonition_start(kind, &admonition_title(kind)).into()));
+                stack.push(true);
+            }
+            Event::Start(Tag::BlockQuote(None)) => {
+                output.push(event);
+                stack.push(false);
+            }
+            Event::End(TagEnd::BlockQuote) => {
+                if stack.pop().unwrap_or(false) {
+                    output.push(Event::Html("</div>\n".into()));
+                } else {
+                    output.push(event);
+                }
+            }
+            _ => output.push(event),
+        }
+    }
+    output
+}
+
+/// expand `:::` fenced admonition containers of markdown `content` to html blocks.
+///
+/// ```text
+/// ::: warning optional title
+/// the markdown body.
+/// :::
+/// ```
+pub fn expand_containers(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut fence: Option<&str> = None;
+    let mut depth = 0;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) && trimmed.trim_start_matches(marker.chars().next().unwrap()).is_empty() {
+                fence = None;
+            }
+            output.push_str(line);
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else if let Some(rest) = trimmed.strip_prefix(":::") {
+            let rest = rest.trim();
+            if rest.is_empty() && depth > 0 {
+                depth -= 1;
+                output.push_str("\n</div>\n\n");
+                continue;
+            }
+            let (kind, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let kind = kind.to_lowercase();
+            if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                depth += 1;
+                let title = match title.trim() {
+                    "" => admonition_title(&kind),
+                    title => title.to_string(),
+                };
+                output.push_str(&admonition_start(&kind, &title));
+                output.push('\n');
+                continue;
+            }
+        }
+        output.push_str(line);
+    }
+    for _ in 0..depth {
+        output.push_str("\n</div>\n");
+    }
+    output
+}
+
+/// the rendered html content of post body port and its table of contents.
+///
+/// headings are given slugified unique ids, and self-link anchors if `heading_anchors` is set,
+/// diagram code blocks are rendered to inline svg.
+pub fn markdown_to_html(content: &str, settings: &Settings, diagrams: &DiagramRenderer) -> (String, Vec<TocItem>) {
+    let events: Vec<Event> = diagrams.render_events(Parser::new_ext(content, markdown_options()).collect());
+
+    let mut titles: Vec<String> = Vec::new();
+    let mut title: Option<String> = None;
+    for event in &events {
+        match event {
+            Event::Start(Tag::Heading { .. }) => title = Some(String::new()),
+            Event::End(TagEnd::Heading(_)) => titles.push(title.take().unwrap_or_default()),
+            Event::Text(s) | Event::Code(s) => {
+                if let Some(ref mut title) = title {
+                    title.push_str(s);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut titles = titles.into_iter();
+    let mut slugger = Slugger::default();
+    let mut headings: Vec<TocItem> = Vec::new();
+    let mut output: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading {
+                level,
+                id: _,
+                classes,
+                attrs,
+            }) => {
+                let title = titles.next().unwrap_or_default();
+                let id = slugger.slug(&title);
+                output.push(Event::Start(Tag::Heading {
+                    level,
+                    id: Some(id.clone().into()),
+                    classes,
+                    attrs,
+                }));
+                headings.push(TocItem {
+                    level: level as u32,
+                    id,
+                    title,
+                    children: Vec::new(),
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if settings.heading_anchors {
+                    if let Some(heading) = headings.last() {
+                        let anchor = format!(r##"<a class="heading-anchor" href="#{}">#</a>"##, heading.id);
+                        output.push(Event::InlineHtml(anchor.into()));
+                    }
+                }
+                output.push(event);
+            }
+            _ => output.push(event),
+        }
+    }
+
     let mut s = String::with_capacity(content.len() * 3 / 2);
-    let p = Parser::new_ext(content, opts);
-    html::push_html(&mut s, p);
-    s
+    html::push_html(&mut s, render_admonitions(output).into_iter());
+    (s, TocItem::build_tree(headings))
+}
+
+/// resolve the internal links of markdown `content`, `[text](@/posts/other.md#anchor)`,
+/// to the post urls of `urls`, which is keyed by post path.
+///
+/// return the resolved content and the unresolved links.
+pub fn resolve_internal_links(content: &str, urls: &HashMap<PathBuf, String>) -> (String, Vec<String>) {
+    if !content.contains("@/") {
+        return (content.to_string(), Vec::new());
+    }
+    let mut dests: Vec<Range<usize>> = Vec::new();
+    let mut parser = Parser::new_ext(content, markdown_options()).into_offset_iter();
+    for (event, range) in parser.by_ref() {
+        if let Event::Start(Tag::Link { ref dest_url, .. }) = event {
+            if dest_url.starts_with("@/") {
+                let start = range.start + content[range.clone()].rfind("](").map(|i| i + 2).unwrap_or(0);
+                dests.extend(link_dest_range(content, start..range.end));
+            }
+        }
+    }
+    for (_, def) in parser.reference_definitions().iter() {
+        if def.dest.starts_with("@/") {
+            let start = def.span.start + content[def.span.clone()].find("]:").map(|i| i + 2).unwrap_or(0);
+            dests.extend(link_dest_range(content, start..def.span.end));
+        }
+    }
+    dests.sort_by_key(|r| r.start);
+    dests.dedup();
+
+    let mut output = String::with_capacity(content.len());
+    let mut unresolved = Vec::new();
+    let mut last = 0;
+    for dest in dests {
+        let link = &content[dest.clone()];
+        output.push_str(&content[last..dest.start]);
+        match resolve_internal_link(link, urls) {
+            Some(url) => output.push_str(&url),
+            None => {
+                output.push_str(link);
+                unresolved.push(link.to_string());
+            }
+        }
+        last = dest.end;
+    }
+    output.push_str(&content[last..]);
+    (output, unresolved)
+}
+
+/// resolve the internal link `@/posts/other.md#anchor` to the post url of `urls`, the path is percent-decoded.
+pub fn resolve_internal_link(link: &str, urls: &HashMap<PathBuf, String>) -> Option<String> {
+    let link = link.strip_prefix("@/")?;
+    let (path, anchor) = match link.find('#') {
+        Some(i) => link.split_at(i),
+        None => (link, ""),
+    };
+    let url = urls.get(Path::new(&percent_decode(path)))?;
+    Some(format!("{}{}", url, anchor))
+}
+
+/// the range of `@/...` link destination in `range` of `content`, the destination may be wrapped in `<>`.
+fn link_dest_range(content: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let s = &content[range.clone()];
+    let trimmed = s.trim_start();
+    let (trimmed, angle) = match trimmed.strip_prefix('<') {
+        Some(rest) => (rest, true),
+        None => (trimmed, false),
+    };
+    if !trimmed.starts_with("@/") {
+        return None;
+    }
+    let start = range.start + s.len() - trimmed.len();
+    let len = if angle {
+        trimmed.find('>')?
+    } else {
+        trimmed
+            .find(|c: char| c.is_whitespace() || c == ')')
+            .unwrap_or(trimmed.len())
+    };
+    Some(start..start + len)
+}
+
+/// decode the percent-encoded `s`, invalid sequences are kept.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// encode the url `path` as tera `urlencode` filter, `/` is kept.
+pub fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// the rendered html of markdown fragment, such as the body of block shortcode.
+pub fn markdown_to_fragment(content: &str) -> String {
+    let mut output = String::new();
+    let events: Vec<Event> = Parser::new_ext(content, markdown_options()).collect();
+    html::push_html(&mut output, render_admonitions(events).into_iter());
+    output
+}
+
+/// the rendered html and plain text summary of post body.
+///
+/// the summary is the part before `<!-- more -->` marker, or the first paragraph if there is no marker.
+pub fn markdown_summary(content: &str) -> (String, String) {
+    let body_events: Vec<Event> = Parser::new_ext(content, markdown_options()).collect();
+    let marker = body_events.iter().position(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => html.contains(MORE_MARKER),
+        _ => false,
+    });
+    let mut events: Vec<Event> = Vec::new();
+    if let Some(marker) = marker {
+        let mut stack: Vec<TagEnd> = Vec::new();
+        for event in body_events.into_iter().take(marker) {
+            match event {
+                Event::Start(ref tag) => stack.push(tag.to_end()),
+                Event::End(_) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            events.push(event);
+        }
+        while let Some(end) = stack.pop() {
+            events.push(Event::End(end));
+        }
+    } else {
+        let mut depth = 0;
+        let mut in_paragraph = false;
+        for event in body_events {
+            match event {
+                Event::Start(Tag::Paragraph) if depth == 0 => {
+                    in_paragraph = true;
+                    depth += 1;
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+            if in_paragraph {
+                events.push(event);
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+    }
+
+    let text = events_to_text(&events);
+    let mut summary = String::new();
+    html::push_html(&mut summary, render_admonitions(events).into_iter());
+    (summary, text)
+}
+
+/// the rendered plain text of post body.
+pub fn markdown_to_text(content: &str) -> String {
+    let events: Vec<Event> = Parser::new_ext(content, markdown_options()).collect();
+    events_to_text(&events)
+}
+
+/// the plain text of markdown events, whitespaces are collapsed.
+fn events_to_text(events: &[Event]) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Text(s) | Event::Code(s) => text.push_str(s),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(end) if is_block_end(end) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// whether `end` closes a block element, the text of blocks is separated by whitespace.
+fn is_block_end(end: &TagEnd) -> bool {
+    !matches!(
+        end,
+        TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link | TagEnd::Image
+    )
+}
+
+/// count the words of `text`, return the number of non-CJK words and CJK characters.
+///
+/// Chinese and Japanese text is not separated by whitespaces, so every Han or kana character is counted as a word,
+/// Korean text is separated by whitespaces and counted by words.
+pub fn count_words(text: &str) -> (usize, usize) {
+    let mut words = 0;
+    let mut cjk_chars = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk_chars += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if c.is_whitespace() {
+            in_word = false;
+        }
+    }
+    (words, cjk_chars)
+}
+
+/// check `c` is a Han or kana character.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'       // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'     // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}'     // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}'     // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}'   // CJK Unified Ideographs Extension B and later
+    )
 }
 
 /// basic error reporting, including the "cause chain".
@@ -48,3 +472,86 @@ pub(crate) fn log_error_chain(mut e: &dyn StdError) {
         e = source;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> HashMap<PathBuf, String> {
+        let mut urls = HashMap::new();
+        urls.insert(PathBuf::from("posts/hello.md"), "/blog/posts/hello.html".to_string());
+        urls.insert(PathBuf::from("posts/a b.md"), "/blog/posts/a%20b.html".to_string());
+        urls
+    }
+
+    #[test]
+    fn expand_nested_containers() {
+        let content = ":::note\nouter\n::: tip Inner\ninner\n:::\n:::\n";
+        let expected = concat!(
+            "<div class=\"admonition admonition-note\">\n<p class=\"admonition-title\">Note</p>\n\n",
+            "outer\n",
+            "<div class=\"admonition admonition-tip\">\n<p class=\"admonition-title\">Inner</p>\n\n",
+            "inner\n",
+            "\n</div>\n\n",
+            "\n</div>\n\n",
+        );
+        assert_eq!(expand_containers(content), expected);
+    }
+
+    #[test]
+    fn skip_containers_in_fenced_code() {
+        let content = "```\n::: note\n:::\n```\n~~~\n::: tip\n:::\n~~~\n";
+        assert_eq!(expand_containers(content), content);
+        assert_eq!(expand_containers(":::\ntext\n"), ":::\ntext\n");
+    }
+
+    #[test]
+    fn close_unclosed_containers() {
+        let content = "::: warning Careful <b>\nbody\n";
+        let expected = concat!(
+            "<div class=\"admonition admonition-warning\">\n<p class=\"admonition-title\">Careful &lt;b&gt;</p>\n\n",
+            "body\n",
+            "\n</div>\n",
+        );
+        assert_eq!(expand_containers(content), expected);
+    }
+
+    #[test]
+    fn count_mixed_words() {
+        assert_eq!(count_words("hello, world! it's 2024"), (4, 0));
+        assert_eq!(count_words("中文字符"), (0, 4));
+        assert_eq!(count_words("ひらがな カタカナ"), (0, 8));
+        assert_eq!(count_words("안녕 하세요 세계"), (3, 0));
+        assert_eq!(count_words("Rust是一门语言, rust-lang 안녕하세요"), (3, 5));
+    }
+
+    #[test]
+    fn resolve_links() {
+        let content = "[a](@/posts/hello.md) [b](@/posts/hello.md#intro \"title\") [c](<@/posts/a b.md>)\n";
+        let (output, unresolved) = resolve_internal_links(content, &urls());
+        assert_eq!(
+            output,
+            "[a](/blog/posts/hello.html) [b](/blog/posts/hello.html#intro \"title\") [c](</blog/posts/a%20b.html>)\n"
+        );
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn resolve_reference_and_encoded_links() {
+        let content = "[a][hello] [b](@/posts/a%20b.md)\n\n[hello]: @/posts/hello.md\n";
+        let (output, unresolved) = resolve_internal_links(content, &urls());
+        assert_eq!(
+            output,
+            "[a][hello] [b](/blog/posts/a%20b.html)\n\n[hello]: /blog/posts/hello.html\n"
+        );
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn report_unresolved_links() {
+        let content = "[a](@/posts/missing.md) `[b](@/posts/code.md)` [c](@/posts/gone.md#x)\n";
+        let (output, unresolved) = resolve_internal_links(content, &urls());
+        assert_eq!(output, content);
+        assert_eq!(unresolved, ["@/posts/missing.md", "@/posts/gone.md#x"]);
+    }
+}